@@ -0,0 +1,81 @@
+use embedded_io_async::{BufRead, Write};
+
+use crate::CopyError;
+
+/// Copy all bytes from a buffered reader to a writer until EOF
+///
+/// The slices returned by `fill_buf()` are written directly to the writer, and only the bytes that were
+/// actually written are consumed from the reader. The writer is not flushed.
+/// The total number of copied bytes is returned.
+pub async fn copy_buf<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<u64, CopyError<R::Error, W::Error>> {
+    let mut copied = 0;
+    loop {
+        let buf = reader.fill_buf().await.map_err(CopyError::Read)?;
+        if buf.is_empty() {
+            return Ok(copied);
+        }
+
+        let written = writer.write(buf).await.map_err(CopyError::Write)?;
+        if written == 0 {
+            return Err(CopyError::WriteZero);
+        }
+        reader.consume(written);
+        copied += written as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_io::ErrorType;
+
+    use crate::asynch::BufferedRead;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn can_copy_buf() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut reader = BufferedRead::new(inner.as_slice(), &mut buf);
+        let mut writer = Vec::new();
+
+        assert_eq!(6, copy_buf(&mut reader, &mut writer).await.unwrap());
+        assert_eq!(&[1, 2, 3, 4, 5, 6], writer.as_slice());
+    }
+
+    #[tokio::test]
+    async fn copy_buf_handles_short_writes() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut reader = BufferedRead::new(inner.as_slice(), &mut buf);
+        let mut writer = ShortWrite::default();
+
+        assert_eq!(6, copy_buf(&mut reader, &mut writer).await.unwrap());
+        assert_eq!(&[1, 2, 3, 4, 5, 6], writer.written.as_slice());
+        assert_eq!(3, writer.writes);
+    }
+
+    #[derive(Default)]
+    struct ShortWrite {
+        written: Vec<u8>,
+        writes: usize,
+    }
+
+    impl ErrorType for ShortWrite {
+        type Error = Infallible;
+    }
+
+    impl Write for ShortWrite {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let len = usize::min(buf.len(), 3);
+            self.written.extend_from_slice(&buf[..len]);
+            self.writes += 1;
+            Ok(len)
+        }
+    }
+}
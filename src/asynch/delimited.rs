@@ -0,0 +1,108 @@
+use embedded_io_async::{BufRead, Read};
+
+/// A [`Read`] and [`BufRead`] over a buffered reader that ends at a delimiter
+///
+/// The DelimitedRead yields the bytes of the underlying reader up to, but not including, the next delimiter.
+/// The delimiter is consumed when it is reached, after which the DelimitedRead reports EOF.
+/// See [`super::BufferedRead::take_until`].
+pub struct DelimitedRead<'a, R: BufRead> {
+    reader: &'a mut R,
+    delim: u8,
+    done: bool,
+}
+
+impl<'a, R: BufRead> DelimitedRead<'a, R> {
+    /// Create a new reader that ends at `delim`
+    pub fn new(reader: &'a mut R, delim: u8) -> Self {
+        Self {
+            reader,
+            delim,
+            done: false,
+        }
+    }
+
+    /// Get whether the delimiter or EOF of the underlying reader was reached
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+impl<R: BufRead> embedded_io::ErrorType for DelimitedRead<'_, R> {
+    type Error = R::Error;
+}
+
+impl<R: BufRead> Read for DelimitedRead<'_, R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let available = self.fill_buf().await?;
+        let len = usize::min(available.len(), buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for DelimitedRead<'_, R> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.done {
+            return Ok(&[]);
+        }
+
+        let (len, found) = {
+            let available = self.reader.fill_buf().await?;
+            match available.iter().position(|&b| b == self.delim) {
+                Some(index) => (index, true),
+                None => (available.len(), false),
+            }
+        };
+        if len == 0 {
+            if found {
+                // Consume the delimiter
+                self.reader.consume(1);
+            }
+            self.done = true;
+            return Ok(&[]);
+        }
+
+        // The bytes are already available, so this does not read from the inner reader
+        let available = self.reader.fill_buf().await?;
+        Ok(&available[..len])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asynch::BufferedRead;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn ends_at_delimiter() {
+        let inner = b"ab,cdefg,,h";
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        let mut record = buffered.take_until(b',');
+        assert_eq!(b"ab", record.fill_buf().await.unwrap());
+        record.consume(2);
+        assert!(record.fill_buf().await.unwrap().is_empty());
+        assert!(record.is_done());
+
+        let mut out = [0; 8];
+        let mut record = buffered.take_until(b',');
+        record.read_exact(&mut out[..5]).await.unwrap();
+        assert_eq!(0, record.read(&mut out).await.unwrap());
+        assert_eq!(b"cdefg", &out[..5]);
+
+        let mut record = buffered.take_until(b',');
+        assert_eq!(0, record.read(&mut out).await.unwrap());
+
+        let mut record = buffered.take_until(b',');
+        assert_eq!(1, record.read(&mut out).await.unwrap());
+        assert_eq!(0, record.read(&mut out).await.unwrap());
+        assert_eq!(b"h", &out[..1]);
+    }
+}
@@ -0,0 +1,140 @@
+use embedded_io_async::{BufRead, Read, ReadExactError, Write};
+
+use super::{BufferedRead, BufferedWrite};
+
+/// A buffered [`Read`] and [`Write`]
+///
+/// The BufferedReadWrite buffers both directions of a full-duplex inner type using two independent buffers.
+pub struct BufferedReadWrite<'r, 'w, T: Read + Write> {
+    inner: BufferedRead<'r, BufferedWrite<'w, T>>,
+}
+
+impl<'r, 'w, T: Read + Write> BufferedReadWrite<'r, 'w, T> {
+    /// Create a new buffered reader and writer
+    pub fn new(inner: T, read_buf: &'r mut [u8], write_buf: &'w mut [u8]) -> Self {
+        Self {
+            inner: BufferedRead::new(BufferedWrite::new(inner, write_buf), read_buf),
+        }
+    }
+
+    /// Get the number of bytes that are readily available
+    pub fn available(&self) -> usize {
+        self.inner.available()
+    }
+
+    /// Get the number of bytes that are currently buffered but not yet written to the inner writer
+    pub fn written(&self) -> usize {
+        self.inner.get_ref().written()
+    }
+
+    /// Get the number of reads that were issued to the inner type
+    pub fn inner_reads(&self) -> u32 {
+        self.inner.inner_reads()
+    }
+
+    /// Get the number of writes that were issued to the inner type
+    pub fn inner_writes(&self) -> u32 {
+        self.inner.get_ref().inner_writes()
+    }
+
+    /// Get the number of flushes that were issued to the inner type
+    pub fn inner_flushes(&self) -> u32 {
+        self.inner.get_ref().inner_flushes()
+    }
+
+    /// Release and get the inner type
+    pub fn release(self) -> T {
+        self.inner.release().release()
+    }
+}
+
+impl<T: Read + Write> embedded_io::ErrorType for BufferedReadWrite<'_, '_, T> {
+    type Error = T::Error;
+}
+
+impl<T: Read + Write> Read for BufferedReadWrite<'_, '_, T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.inner.read(buf).await
+    }
+
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ReadExactError<Self::Error>> {
+        self.inner.read_exact(buf).await
+    }
+}
+
+impl<T: Read + Write> BufRead for BufferedReadWrite<'_, '_, T> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        self.inner.fill_buf().await
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl<T: Read + Write> Write for BufferedReadWrite<'_, '_, T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.inner.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_io::ErrorType;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_and_writes_are_independent() {
+        let inner = Duplex {
+            rx: &[1, 2, 3, 4, 5, 6],
+            tx: Vec::new(),
+        };
+        let mut read_buf = [0; 4];
+        let mut write_buf = [0; 4];
+        let mut buffered = BufferedReadWrite::new(inner, &mut read_buf, &mut write_buf);
+
+        assert_eq!(&[1, 2, 3, 4], buffered.fill_buf().await.unwrap());
+        buffered.consume(1);
+        assert_eq!(3, buffered.available());
+
+        assert_eq!(2, buffered.write(&[7, 8]).await.unwrap());
+        assert_eq!(2, buffered.written());
+        assert_eq!(3, buffered.available());
+
+        buffered.flush().await.unwrap();
+        assert_eq!(0, buffered.written());
+        assert_eq!(&[2, 3, 4], buffered.fill_buf().await.unwrap());
+
+        let inner = buffered.release();
+        assert_eq!(&[5, 6], inner.rx);
+        assert_eq!(&[7, 8], inner.tx.as_slice());
+    }
+
+    struct Duplex {
+        rx: &'static [u8],
+        tx: Vec<u8>,
+    }
+
+    impl ErrorType for Duplex {
+        type Error = Infallible;
+    }
+
+    impl Read for Duplex {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.rx.read(buf).await
+        }
+    }
+
+    impl Write for Duplex {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.tx.write(buf).await
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll};
+use std::io;
+
+use embedded_io::Error;
+use embedded_io_async::{BufRead, Read, Write};
+use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+
+/// An adapter implementing the `futures-io` traits for an `embedded-io-async` type
+///
+/// The adapter is typically used with the buffered types, for example to pass a [`super::BufferedRead`]
+/// to code that expects an [`AsyncBufRead`].
+///
+/// Each `poll_*` call creates the corresponding future of the inner type and polls it once.
+/// A future that is not ready is dropped and created again on the next poll,
+/// so the operations of the inner type must be cancel safe.
+pub struct FuturesAdapter<T> {
+    inner: T,
+}
+
+impl<T> FuturesAdapter<T> {
+    /// Create a new adapter
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Get a reference to the inner type
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner type
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Release and get the inner type
+    pub fn release(self) -> T {
+        self.inner
+    }
+}
+
+fn to_io_error<E: Error>(err: E) -> io::Error {
+    io::Error::new(err.kind().into(), format!("{:?}", err))
+}
+
+fn poll_once<F: Future<Output = Result<R, E>>, R, E: Error>(
+    fut: F,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<R>> {
+    pin!(fut).poll(cx).map_err(to_io_error)
+}
+
+impl<T: Read + Unpin> AsyncRead for FuturesAdapter<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_once(self.get_mut().inner.read(buf), cx)
+    }
+}
+
+impl<T: Read + BufRead + Unpin> AsyncBufRead for FuturesAdapter<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        poll_once(self.get_mut().inner.fill_buf(), cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().inner.consume(amt)
+    }
+}
+
+impl<T: Write + Unpin> AsyncWrite for FuturesAdapter<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_once(self.get_mut().inner.write(buf), cx)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        poll_once(self.get_mut().inner.flush(), cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        poll_once(self.get_mut().inner.flush(), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::poll_fn;
+
+    use crate::asynch::{BufferedRead, BufferedWrite};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn can_read_from_buffered_read() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut adapter = FuturesAdapter::new(BufferedRead::new(inner.as_slice(), &mut buf));
+
+        let filled = poll_fn(|cx| {
+            Pin::new(&mut adapter)
+                .poll_fill_buf(cx)
+                .map_ok(|b| b.to_vec())
+        })
+        .await
+        .unwrap();
+        assert_eq!(&[1, 2, 3, 4], filled.as_slice());
+        Pin::new(&mut adapter).consume(1);
+
+        let mut read = [0; 2];
+        let len = poll_fn(|cx| Pin::new(&mut adapter).poll_read(cx, &mut read))
+            .await
+            .unwrap();
+        assert_eq!(2, len);
+        assert_eq!(&[2, 3], &read);
+        assert_eq!(1, adapter.get_ref().available());
+    }
+
+    #[tokio::test]
+    async fn can_write_to_buffered_write() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 4];
+        let mut adapter = FuturesAdapter::new(BufferedWrite::new(&mut inner, &mut buf));
+
+        let len = poll_fn(|cx| Pin::new(&mut adapter).poll_write(cx, &[1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(2, len);
+        assert_eq!(2, adapter.get_ref().written());
+
+        poll_fn(|cx| Pin::new(&mut adapter).poll_close(cx))
+            .await
+            .unwrap();
+        assert_eq!(&[1, 2], adapter.release().release().as_slice());
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::vec::Vec;
+
+use embedded_io_async::Write;
+
+use super::BufferedWrite;
+
+/// A buffered [`Write`] with a growable buffer
+///
+/// The GrowableBufferedWrite grows its `Vec<u8>` buffer when a write would otherwise cause the buffer
+/// to be written to the inner writer. This allows an entire message to be accumulated and written in one
+/// write when the buffer is flushed. If a maximum capacity is specified, the buffer is not grown beyond it,
+/// and the writer behaves as a [`BufferedWrite`] once the maximum capacity is reached.
+pub struct GrowableBufferedWrite<T: Write> {
+    inner: BufferedWrite<'static, T, Vec<u8>>,
+    max_capacity: Option<usize>,
+}
+
+impl<T: Write> GrowableBufferedWrite<T> {
+    /// Create a new buffered writer with a buffer that grows without limit
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: BufferedWrite::with_storage(inner, Vec::new()),
+            max_capacity: None,
+        }
+    }
+
+    /// Create a new buffered writer with a buffer that grows up to `max_capacity` bytes
+    pub fn with_max_capacity(inner: T, max_capacity: usize) -> Self {
+        Self {
+            inner: BufferedWrite::with_storage(inner, Vec::new()),
+            max_capacity: Some(max_capacity),
+        }
+    }
+
+    /// Get whether there are any bytes currently buffered
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Get the number of bytes that are currently buffered but not yet written to the inner writer
+    pub fn written(&self) -> usize {
+        self.inner.written()
+    }
+
+    /// Get the current capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Get the maximum capacity of the buffer, if any
+    pub fn max_capacity(&self) -> Option<usize> {
+        self.max_capacity
+    }
+
+    /// Get the currently buffered, written bytes
+    pub fn buffer(&self) -> &[u8] {
+        self.inner.buffer()
+    }
+
+    /// Get a reference to the inner writer
+    pub fn get_ref(&self) -> &T {
+        self.inner.get_ref()
+    }
+
+    /// Get a mutable reference to the inner writer, regardless of any currently buffered bytes
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    /// Release and get the inner writer
+    pub fn release(self) -> T {
+        self.inner.release()
+    }
+
+    /// Flush the buffered bytes and release the inner writer
+    pub async fn finish(self) -> Result<T, T::Error> {
+        self.inner.finish().await
+    }
+
+    /// Grow the buffer such that `len` more bytes can be buffered without the buffer becoming full
+    fn grow_for(&mut self, len: usize) {
+        // The buffer is written to the inner writer as soon as it becomes full, so keep one spare byte
+        let required = self.inner.written() + len + 1;
+        let capacity = self.inner.capacity();
+        if required <= capacity {
+            return;
+        }
+
+        let mut new_capacity = usize::max(required, 2 * capacity);
+        if let Some(max_capacity) = self.max_capacity {
+            new_capacity = usize::min(new_capacity, max_capacity);
+        }
+        if new_capacity > capacity {
+            self.inner.storage_mut().resize(new_capacity, 0);
+        }
+    }
+}
+
+impl<T: Write> embedded_io::ErrorType for GrowableBufferedWrite<T> {
+    type Error = T::Error;
+}
+
+impl<T: Write> Write for GrowableBufferedWrite<T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.grow_for(buf.len());
+        self.inner.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn grows_instead_of_writing() {
+        let mut inner = Vec::new();
+        let mut buffered = GrowableBufferedWrite::new(&mut inner);
+
+        buffered.write_all(&[1, 2, 3, 4]).await.unwrap();
+        buffered
+            .write_all(&[5, 6, 7, 8, 9, 10, 11, 12])
+            .await
+            .unwrap();
+        assert_eq!(12, buffered.written());
+        assert!(buffered.capacity() > 12);
+        assert!(buffered.get_ref().is_empty());
+
+        buffered.flush().await.unwrap();
+        assert!(buffered.is_empty());
+        assert_eq!(
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12],
+            buffered.get_ref().as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_when_max_capacity_is_reached() {
+        let mut inner = Vec::new();
+        let mut buffered = GrowableBufferedWrite::with_max_capacity(&mut inner, 4);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        assert_eq!(2, buffered.written());
+        assert!(buffered.get_ref().is_empty());
+
+        assert_eq!(2, buffered.write(&[3, 4, 5]).await.unwrap());
+        assert_eq!(4, buffered.capacity());
+        assert!(buffered.is_empty());
+        assert_eq!(&[1, 2, 3, 4], buffered.get_ref().as_slice());
+    }
+}
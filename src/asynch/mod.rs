@@ -1,9 +1,193 @@
+mod copy;
+mod delimited;
+mod duplex;
+#[cfg(feature = "futures")]
+mod futures;
+#[cfg(feature = "alloc")]
+mod growable;
 mod read;
 mod write;
+mod zeroize;
 
-pub use read::BufferedRead;
-pub use write::BufferedWrite;
+pub use copy::copy_buf;
+pub use delimited::DelimitedRead;
+pub use duplex::BufferedReadWrite;
+#[cfg(feature = "futures")]
+pub use futures::FuturesAdapter;
+#[cfg(feature = "alloc")]
+pub use growable::GrowableBufferedWrite;
+pub use read::{BufferedRead, BufferedReadN, Drain};
+pub use write::{BufferedWrite, BufferedWriteN};
+pub use zeroize::ZeroizeOnDrop;
 
-/// Unable to bypass the current buffered reader or writer because there are buffered bytes.
+/// A tap that observes the bytes passing through a buffered reader or writer
+///
+/// The tap is implemented for all `FnMut(&[u8])` closures.
+pub trait Tap {
+    /// Observe a chunk of bytes
+    fn tap(&mut self, bytes: &[u8]);
+}
+
+/// A [`Tap`] that ignores all bytes
+pub struct NoTap;
+
+impl Tap for NoTap {
+    fn tap(&mut self, _bytes: &[u8]) {}
+}
+
+impl<F: FnMut(&[u8])> Tap for F {
+    fn tap(&mut self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+/// Unable to bypass the current buffered reader or writer because there are buffered bytes, or the reader is marked.
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct BypassError;
+
+impl core::fmt::Display for BypassError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("cannot bypass: buffered bytes or mark present")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BypassError {}
+
+impl embedded_io::Error for BypassError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
+    }
+}
+
+/// Unable to reset the buffered reader because there is no mark.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NoMarkError;
+
+impl core::fmt::Display for NoMarkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("cannot reset: no mark")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NoMarkError {}
+
+impl embedded_io::Error for NoMarkError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
+    }
+}
+
+/// The requested number of bytes does not fit in the capacity of the buffer.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CapacityError;
+
+impl core::fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("capacity exceeded: bytes do not fit in the buffer")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+impl embedded_io::Error for CapacityError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::OutOfMemory
+    }
+}
+
+/// Unable to consume bytes from the buffered reader because fewer bytes are available.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConsumeError;
+
+impl core::fmt::Display for ConsumeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("cannot consume: not enough bytes available")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConsumeError {}
+
+impl embedded_io::Error for ConsumeError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::InvalidInput
+    }
+}
+
+/// Error returned when a number of bytes must be buffered at once by a reader.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FillError<E> {
+    /// The requested number of bytes exceeds the capacity of the buffer, or the part of it not kept for a mark.
+    Capacity,
+    /// An EOF error was encountered before the requested number of bytes were buffered.
+    UnexpectedEof,
+    /// Error returned by the inner reader.
+    Other(E),
+}
+
+impl<E> From<E> for FillError<E> {
+    fn from(err: E) -> Self {
+        Self::Other(err)
+    }
+}
+
+/// Error returned when the buffered bytes must be written to the inner writer.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlushError<E> {
+    /// The inner writer repeatedly returned `Ok(0)` and is considered stalled.
+    WriteZero,
+    /// Error returned by the inner writer.
+    Other(E),
+}
+
+impl<E> From<E> for FlushError<E> {
+    fn from(err: E) -> Self {
+        Self::Other(err)
+    }
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for FlushError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Self::WriteZero => embedded_io::ErrorKind::WriteZero,
+            Self::Other(err) => err.kind(),
+        }
+    }
+}
+
+/// Error returned when room for a number of bytes must be made in the buffer of a writer.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReserveError<E> {
+    /// The requested number of bytes exceeds the capacity of the buffer.
+    Capacity,
+    /// The inner writer repeatedly returned `Ok(0)` and is considered stalled.
+    WriteZero,
+    /// Error returned by the inner writer.
+    Other(E),
+}
+
+impl<E> From<E> for ReserveError<E> {
+    fn from(err: E) -> Self {
+        Self::Other(err)
+    }
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for ReserveError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Self::Capacity => embedded_io::ErrorKind::OutOfMemory,
+            Self::WriteZero => embedded_io::ErrorKind::WriteZero,
+            Self::Other(err) => err.kind(),
+        }
+    }
+}
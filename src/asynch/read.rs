@@ -1,26 +1,44 @@
-use embedded_io_async::{BufRead, Read, Write};
+use core::marker::PhantomData;
 
-use super::BypassError;
+use embedded_io_async::{BufRead, Read, ReadExactError, ReadReady, Write, WriteReady};
+
+use super::{
+    BypassError, CapacityError, ConsumeError, DelimitedRead, FillError, NoMarkError, NoTap, Tap,
+    ZeroizeOnDrop,
+};
 
 /// A buffered [`Read`]
 ///
 /// The BufferedRead will read into the provided buffer to avoid small reads to the inner reader.
-pub struct BufferedRead<'buf, T: Read> {
+/// The buffer is typically a borrowed `&mut [u8]`, but can be any storage that implements
+/// `AsRef<[u8]>` and `AsMut<[u8]>`, such as an owned array.
+pub struct BufferedRead<'buf, T: Read, B = &'buf mut [u8], F = NoTap> {
     inner: T,
-    buf: &'buf mut [u8],
+    buf: B,
     offset: usize,
     available: usize,
+    eof: bool,
+    mark: Option<usize>,
+    /// The number of bytes at `offset` that were already tapped before a reset to the mark
+    replay: usize,
+    read_total: u64,
+    bypass_reads: u32,
+    buffered_reads: u32,
+    inner_reads: u32,
+    bypass_threshold: Option<usize>,
+    tap: F,
+    _buf: PhantomData<&'buf mut [u8]>,
 }
 
+/// A buffered [`Read`] that owns its buffer of `N` bytes
+///
+/// Create it with [`BufferedRead::with_storage`].
+pub type BufferedReadN<T, const N: usize> = BufferedRead<'static, T, [u8; N]>;
+
 impl<'buf, T: Read> BufferedRead<'buf, T> {
     /// Create a new buffered reader
     pub fn new(inner: T, buf: &'buf mut [u8]) -> Self {
-        Self {
-            inner,
-            buf,
-            offset: 0,
-            available: 0,
-        }
+        Self::with_storage(inner, buf)
     }
 
     /// Create a new buffered reader with the first `available` bytes readily available at `offset`.
@@ -28,15 +46,118 @@ impl<'buf, T: Read> BufferedRead<'buf, T> {
     /// This is useful if for some reason the inner reader was previously consumed by a greedy reader
     /// in a way such that the BufferedRead must inherit these excess bytes.
     pub fn new_with_data(inner: T, buf: &'buf mut [u8], offset: usize, available: usize) -> Self {
-        assert!(offset + available <= buf.len());
+        Self::with_storage_and_data(inner, buf, offset, available)
+    }
+
+    /// Create a new buffered reader with the first `available` bytes readily available at `offset`,
+    /// failing if the bytes do not fit in the buffer
+    ///
+    /// See [`BufferedRead::new_with_data`].
+    pub fn try_new_with_data(
+        inner: T,
+        buf: &'buf mut [u8],
+        offset: usize,
+        available: usize,
+    ) -> Result<Self, CapacityError> {
+        match offset.checked_add(available) {
+            Some(end) if end <= buf.len() => Ok(Self::new_with_data(inner, buf, offset, available)),
+            _ => Err(CapacityError),
+        }
+    }
+}
+
+impl<'buf, T: Read, B: AsRef<[u8]> + AsMut<[u8]>> BufferedRead<'buf, T, B> {
+    /// Create a new buffered reader with any buffer storage, such as an owned array
+    pub fn with_storage(inner: T, buf: B) -> Self {
+        Self::with_storage_and_data(inner, buf, 0, 0)
+    }
+
+    /// Create a new buffered reader with any buffer storage and the first `available` bytes readily available at `offset`
+    ///
+    /// See [`BufferedRead::new_with_data`].
+    pub fn with_storage_and_data(inner: T, buf: B, offset: usize, available: usize) -> Self {
+        assert!(offset + available <= buf.as_ref().len());
         Self {
             inner,
             buf,
             offset,
             available,
+            eof: false,
+            mark: None,
+            replay: 0,
+            read_total: 0,
+            bypass_reads: 0,
+            buffered_reads: 0,
+            inner_reads: 0,
+            bypass_threshold: None,
+            tap: NoTap,
+            _buf: PhantomData,
+        }
+    }
+}
+
+impl<'buf, T: Read, B: AsRef<[u8]> + AsMut<[u8]>, F: Tap> BufferedRead<'buf, T, B, F> {
+    /// Set a tap that is called with the bytes delivered to the caller
+    ///
+    /// The tap is called exactly once for every byte, in the order the bytes are read or consumed,
+    /// regardless of whether they are buffered or read directly from the inner reader.
+    /// Bytes that are only peeked are not seen by the tap until they are consumed.
+    pub fn with_tap<G: FnMut(&[u8])>(self, tap: G) -> BufferedRead<'buf, T, B, G> {
+        BufferedRead {
+            inner: self.inner,
+            buf: self.buf,
+            offset: self.offset,
+            available: self.available,
+            eof: self.eof,
+            mark: self.mark,
+            replay: self.replay,
+            read_total: self.read_total,
+            bypass_reads: self.bypass_reads,
+            buffered_reads: self.buffered_reads,
+            inner_reads: self.inner_reads,
+            bypass_threshold: self.bypass_threshold,
+            tap,
+            _buf: PhantomData,
+        }
+    }
+
+    /// Overwrite the buffer with zeros when the reader is dropped
+    ///
+    /// This is useful if sensitive data passes through the buffer.
+    pub fn zeroize_on_drop(self) -> BufferedRead<'buf, T, ZeroizeOnDrop<B>, F> {
+        BufferedRead {
+            inner: self.inner,
+            buf: ZeroizeOnDrop::new(self.buf),
+            offset: self.offset,
+            available: self.available,
+            eof: self.eof,
+            mark: self.mark,
+            replay: self.replay,
+            read_total: self.read_total,
+            bypass_reads: self.bypass_reads,
+            buffered_reads: self.buffered_reads,
+            inner_reads: self.inner_reads,
+            bypass_threshold: self.bypass_threshold,
+            tap: self.tap,
+            _buf: PhantomData,
         }
     }
 
+    /// Set the size at which reads bypass the buffer and go directly to the inner reader
+    ///
+    /// The threshold defaults to, and is clamped to at most, the capacity of the buffer.
+    pub fn with_bypass_threshold(mut self, threshold: usize) -> Self {
+        self.bypass_threshold = Some(threshold);
+        self
+    }
+
+    /// Get the size at which reads bypass the buffer and go directly to the inner reader
+    pub fn bypass_threshold(&self) -> usize {
+        let capacity = self.buf.as_ref().len();
+        self.bypass_threshold
+            .map_or(capacity, |threshold| usize::min(threshold, capacity))
+    }
+
     /// Get whether there are any bytes readily available
     pub fn is_empty(&self) -> bool {
         self.available == 0
@@ -47,25 +168,564 @@ impl<'buf, T: Read> BufferedRead<'buf, T> {
         self.available
     }
 
-    /// Get the inner reader if there are no currently buffered, available bytes
+    /// Get the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.buf.as_ref().len()
+    }
+
+    /// Get whether `n` bytes fit in the buffer at once
+    pub fn can_hold(&self, n: usize) -> bool {
+        n <= self.buf.as_ref().len()
+    }
+
+    /// Get the total number of bytes that have been read or consumed by the caller
+    ///
+    /// Bytes that are buffered but not yet read or consumed are not included.
+    pub fn bytes_read(&self) -> u64 {
+        self.read_total
+    }
+
+    /// Get the number of reads that bypassed the buffer and read directly from the inner reader
+    pub fn bypass_count(&self) -> u32 {
+        self.bypass_reads
+    }
+
+    /// Get the number of reads that were served from the buffer
+    pub fn buffered_count(&self) -> u32 {
+        self.buffered_reads
+    }
+
+    /// Get the number of reads that were issued to the inner reader
+    ///
+    /// Reads and consumes that are satisfied by the buffer are not included.
+    pub fn inner_reads(&self) -> u32 {
+        self.inner_reads
+    }
+
+    /// Get whether the inner reader is at EOF and there are no bytes readily available
+    ///
+    /// The inner reader is read if the buffer is empty. Once EOF is observed it is cached such that
+    /// no further reads are issued to the inner reader.
+    pub async fn is_eof(&mut self) -> Result<bool, T::Error> {
+        if self.available == 0 && !self.eof {
+            self.refill().await?;
+        }
+
+        Ok(self.available == 0 && self.eof)
+    }
+
+    /// Clear the currently buffered, available bytes
+    pub fn clear(&mut self) {
+        self.offset = 0;
+        self.available = 0;
+        self.mark = None;
+        self.replay = 0;
+    }
+
+    /// Mark the current position so that the reader can later be reset to it
+    ///
+    /// While the mark is set, the bytes consumed after it are kept in the buffer and reads do not bypass the buffer.
+    /// If keeping the bytes would prevent the buffer from being filled, [`FillError::Capacity`] is returned by
+    /// [`BufferedRead::peek_exact`], [`BufferedRead::fill_buf_at_least`] and [`BufferedRead::fill_buf_more`],
+    /// and the mark is kept. The other methods, including the [`Read`] and [`BufRead`] methods, cannot return
+    /// such an error, so they discard the mark to make progress, after which [`BufferedRead::reset_to_mark`]
+    /// returns [`NoMarkError`].
+    pub fn mark(&mut self) {
+        self.mark = Some(self.offset);
+    }
+
+    /// Reset the reader to the marked position, making the bytes consumed after it available again
+    ///
+    /// The bytes are not seen again by the tap when they are read or consumed for the second time.
+    /// The mark remains set. [`NoMarkError`] is returned if there is no mark, or if it was discarded.
+    pub fn reset_to_mark(&mut self) -> Result<(), NoMarkError> {
+        let mark = self.mark.ok_or(NoMarkError)?;
+        let rewound = self.offset - mark;
+        self.offset = mark;
+        self.available += rewound;
+        self.replay += rewound;
+        self.read_total -= rewound as u64;
+        Ok(())
+    }
+
+    /// Clear the mark, allowing the bytes consumed after it to be overwritten
+    pub fn clear_mark(&mut self) {
+        self.mark = None;
+    }
+
+    /// Prime the buffer with one read from the inner reader, and get the number of bytes that were read
+    ///
+    /// This is a no-op returning 0 if there are already available bytes in the buffer, or if the reader is at EOF.
+    pub async fn prefill(&mut self) -> Result<usize, T::Error> {
+        if self.available > 0 || self.eof {
+            return Ok(0);
+        }
+
+        self.refill().await?;
+        Ok(self.available)
+    }
+
+    /// Get the currently buffered, available bytes
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf.as_ref()[self.offset..self.offset + self.available]
+    }
+
+    /// Get the bytes that are readily available without reading from the inner reader
+    ///
+    /// This is the same as [`BufferedRead::buffer`].
+    pub fn peek(&self) -> &[u8] {
+        self.buffer()
+    }
+
+    /// Get the next byte without consuming it, filling the buffer if it is empty
+    ///
+    /// `None` is returned if the inner reader is at EOF.
+    pub async fn peek_byte(&mut self) -> Result<Option<u8>, T::Error> {
+        Ok(self.fill_buf().await?.first().copied())
+    }
+
+    /// Get exactly `n` bytes without consuming them, reading from the inner reader until they are available
+    pub async fn peek_exact(&mut self, n: usize) -> Result<&[u8], FillError<T::Error>> {
+        if n > self.buf.as_ref().len() {
+            return Err(FillError::Capacity);
+        }
+
+        if !self.fill_to(n).await? {
+            return Err(FillError::UnexpectedEof);
+        }
+
+        Ok(&self.buf.as_ref()[self.offset..self.offset + n])
+    }
+
+    /// Read from the inner reader until at least `min` bytes are available, and get all the available bytes
+    ///
+    /// Fewer than `min` bytes are returned if the inner reader reaches EOF.
+    pub async fn fill_buf_at_least(&mut self, min: usize) -> Result<&[u8], FillError<T::Error>> {
+        if min > self.buf.as_ref().len() {
+            return Err(FillError::Capacity);
+        }
+
+        self.fill_to(min).await?;
+
+        Ok(&self.buf.as_ref()[self.offset..self.offset + self.available])
+    }
+
+    /// Read bytes into `out` until and including the first `delim`, returning the number of bytes read
+    ///
+    /// Reading stops early if `out` is filled before `delim` is found, or if the inner reader is at EOF.
+    pub async fn read_until(&mut self, delim: u8, out: &mut [u8]) -> Result<usize, T::Error> {
+        let mut read = 0;
+        while read < out.len() {
+            let available = self.fill_buf().await?;
+            if available.is_empty() {
+                break;
+            }
+
+            let max = usize::min(available.len(), out.len() - read);
+            let (len, found) = match available[..max].iter().position(|&b| b == delim) {
+                Some(index) => (index + 1, true),
+                None => (max, false),
+            };
+            out[read..read + len].copy_from_slice(&available[..len]);
+            self.consume(len);
+            read += len;
+
+            if found {
+                break;
+            }
+        }
+
+        Ok(read)
+    }
+
+    /// Read more bytes from the inner reader while keeping the bytes that are already available
+    ///
+    /// The available bytes are moved to the front of the buffer to make room for the new bytes,
+    /// so that the returned window grows across calls. No read is issued if the buffer is full.
+    /// [`FillError::Capacity`] is returned if the bytes kept for a mark leave no room for new bytes.
+    pub async fn fill_buf_more(&mut self) -> Result<&[u8], FillError<T::Error>> {
+        if !self.eof && self.available < self.buf.as_ref().len() {
+            self.compact();
+            if !self.make_room(self.available + 1) {
+                return Err(FillError::Capacity);
+            }
+            let end = self.offset + self.available;
+            self.inner_reads = self.inner_reads.wrapping_add(1);
+            let read = self.inner.read(&mut self.buf.as_mut()[end..]).await?;
+            self.available += read;
+            self.eof = read == 0;
+        }
+
+        Ok(&self.buf.as_ref()[self.offset..self.offset + self.available])
+    }
+
+    /// Skip `n` bytes, returning the number of bytes skipped
+    ///
+    /// Fewer than `n` bytes are skipped if the inner reader reaches EOF.
+    pub async fn skip(&mut self, n: usize) -> Result<usize, T::Error> {
+        let mut skipped = 0;
+        while skipped < n {
+            let available = self.fill_buf().await?.len();
+            if available == 0 {
+                break;
+            }
+
+            let len = usize::min(available, n - skipped);
+            self.consume(len);
+            skipped += len;
+        }
+
+        Ok(skipped)
+    }
+
+    /// Get the unused tail of the buffer for the caller to read directly into
+    ///
+    /// The available bytes are moved to the front of the buffer first, so that the returned slice is as large as possible.
+    /// Call [`BufferedRead::commit`] to make the bytes that were read into the slice available.
+    pub fn spare_capacity_mut(&mut self) -> &mut [u8] {
+        if self.offset > 0 {
+            self.compact();
+        }
+        let end = self.offset + self.available;
+        &mut self.buf.as_mut()[end..]
+    }
+
+    /// Make `n` bytes that were read into the slice returned by [`BufferedRead::spare_capacity_mut`] available
+    pub fn commit(&mut self, n: usize) {
+        assert!(self.offset + self.available + n <= self.buf.as_ref().len());
+        self.available += n;
+    }
+
+    /// Get an iterator that consumes the available bytes without reading from the inner reader
+    ///
+    /// Bytes are consumed as they are yielded, and the iterator ends when the buffer is empty.
+    pub fn drain(&mut self) -> Drain<'_, F> {
+        Drain {
+            buf: self.buf.as_ref(),
+            offset: &mut self.offset,
+            available: &mut self.available,
+            read_total: &mut self.read_total,
+            replay: &mut self.replay,
+            tap: &mut self.tap,
+        }
+    }
+
+    /// Read the available bytes into `out` without reading from the inner reader
+    ///
+    /// The number of bytes read is returned, which is 0 if the buffer is empty.
+    pub fn read_buffered(&mut self, out: &mut [u8]) -> usize {
+        let len = usize::min(self.available, out.len());
+        if len > 0 {
+            out[..len].copy_from_slice(&self.buf.as_ref()[self.offset..self.offset + len]);
+            self.consume(len);
+            self.buffered_reads = self.buffered_reads.wrapping_add(1);
+        }
+        len
+    }
+
+    /// Get a reader that yields the bytes up to the next `delim`, and then reports EOF
+    ///
+    /// The delimiter itself is consumed but not yielded. See [`DelimitedRead`].
+    pub fn take_until(&mut self, delim: u8) -> DelimitedRead<'_, Self> {
+        DelimitedRead::new(self, delim)
+    }
+
+    /// Consume all the available bytes, and get the number of consumed bytes
+    pub fn consume_all(&mut self) -> usize {
+        let consumed = self.available;
+        self.consume(consumed);
+        consumed
+    }
+
+    /// Consume `amt` of the available bytes, failing if fewer than `amt` bytes are available
+    pub fn try_consume(&mut self, amt: usize) -> Result<(), ConsumeError> {
+        if amt > self.available {
+            return Err(ConsumeError);
+        }
+        self.consume(amt);
+        Ok(())
+    }
+
+    /// Read all bytes until EOF and append them to `out`
+    ///
+    /// The inner reader is read in chunks of the buffer capacity. The number of appended bytes is returned.
+    #[cfg(feature = "alloc")]
+    pub async fn read_to_end(&mut self, out: &mut alloc::vec::Vec<u8>) -> Result<usize, T::Error> {
+        let mut read = 0;
+        loop {
+            let available = self.fill_buf().await?;
+            if available.is_empty() {
+                break;
+            }
+
+            let len = available.len();
+            out.extend_from_slice(available);
+            self.consume(len);
+            read += len;
+        }
+
+        Ok(read)
+    }
+
+    /// Read exactly `N` bytes into an array
+    pub async fn read_array<const N: usize>(
+        &mut self,
+    ) -> Result<[u8; N], ReadExactError<T::Error>> {
+        let mut bytes = [0; N];
+        self.read_exact(&mut bytes).await?;
+        Ok(bytes)
+    }
+
+    /// Read a little endian `u16`
+    pub async fn read_u16_le(&mut self) -> Result<u16, ReadExactError<T::Error>> {
+        Ok(u16::from_le_bytes(self.read_array().await?))
+    }
+
+    /// Read a little endian `i16`
+    pub async fn read_i16_le(&mut self) -> Result<i16, ReadExactError<T::Error>> {
+        Ok(i16::from_le_bytes(self.read_array().await?))
+    }
+
+    /// Read a little endian `u32`
+    pub async fn read_u32_le(&mut self) -> Result<u32, ReadExactError<T::Error>> {
+        Ok(u32::from_le_bytes(self.read_array().await?))
+    }
+
+    /// Read a little endian `i32`
+    pub async fn read_i32_le(&mut self) -> Result<i32, ReadExactError<T::Error>> {
+        Ok(i32::from_le_bytes(self.read_array().await?))
+    }
+
+    /// Read a little endian `u64`
+    pub async fn read_u64_le(&mut self) -> Result<u64, ReadExactError<T::Error>> {
+        Ok(u64::from_le_bytes(self.read_array().await?))
+    }
+
+    /// Read a little endian `i64`
+    pub async fn read_i64_le(&mut self) -> Result<i64, ReadExactError<T::Error>> {
+        Ok(i64::from_le_bytes(self.read_array().await?))
+    }
+
+    /// Read a big endian `u16`
+    pub async fn read_u16_be(&mut self) -> Result<u16, ReadExactError<T::Error>> {
+        Ok(u16::from_be_bytes(self.read_array().await?))
+    }
+
+    /// Read a big endian `i16`
+    pub async fn read_i16_be(&mut self) -> Result<i16, ReadExactError<T::Error>> {
+        Ok(i16::from_be_bytes(self.read_array().await?))
+    }
+
+    /// Read a big endian `u32`
+    pub async fn read_u32_be(&mut self) -> Result<u32, ReadExactError<T::Error>> {
+        Ok(u32::from_be_bytes(self.read_array().await?))
+    }
+
+    /// Read a big endian `i32`
+    pub async fn read_i32_be(&mut self) -> Result<i32, ReadExactError<T::Error>> {
+        Ok(i32::from_be_bytes(self.read_array().await?))
+    }
+
+    /// Read a big endian `u64`
+    pub async fn read_u64_be(&mut self) -> Result<u64, ReadExactError<T::Error>> {
+        Ok(u64::from_be_bytes(self.read_array().await?))
+    }
+
+    /// Read a big endian `i64`
+    pub async fn read_i64_be(&mut self) -> Result<i64, ReadExactError<T::Error>> {
+        Ok(i64::from_be_bytes(self.read_array().await?))
+    }
+
+    /// Get the inner reader if there are no currently buffered, available bytes, and no mark
     pub fn bypass(&mut self) -> Result<&mut T, BypassError> {
-        match self.available {
-            0 => Ok(&mut self.inner),
+        match (self.available, self.mark) {
+            (0, None) => Ok(&mut self.inner),
+            _ => Err(BypassError),
+        }
+    }
+
+    /// Get the inner reader if there are no currently buffered, available bytes, and no mark, and rent the buffer
+    pub fn bypass_with_buf(&mut self) -> Result<(&mut T, &mut [u8]), BypassError> {
+        match (self.available, self.mark) {
+            (0, None) => Ok((&mut self.inner, self.buf.as_mut())),
             _ => Err(BypassError),
         }
     }
 
+    /// Split the reader to get the inner components
+    ///
+    /// The available bytes are located in the buffer at the returned offset.
+    pub fn split(&mut self) -> (&mut T, &mut [u8], usize, usize) {
+        (
+            &mut self.inner,
+            self.buf.as_mut(),
+            self.offset,
+            self.available,
+        )
+    }
+
+    /// Get the currently buffered, available bytes together with the inner reader
+    ///
+    /// This allows the buffered prefix of a frame to be processed before its tail is read directly from the inner reader.
+    /// The returned bytes are not consumed. [`BypassError`] is returned if there is a mark,
+    /// as reading from the inner reader would invalidate it.
+    pub fn split_buffered(&mut self) -> Result<(&[u8], &mut T), BypassError> {
+        if self.mark.is_some() {
+            return Err(BypassError);
+        }
+
+        Ok((
+            &self.buf.as_ref()[self.offset..self.offset + self.available],
+            &mut self.inner,
+        ))
+    }
+
+    /// Get a reference to the inner reader
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner reader, regardless of any currently buffered bytes
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
     /// Release and get the inner reader
     pub fn release(self) -> T {
         self.inner
     }
+
+    /// Release and get the inner components
+    ///
+    /// The returned offset and available count can be passed to [`BufferedRead::with_storage_and_data`]
+    /// to construct a new reader that inherits the currently buffered bytes.
+    pub fn into_parts(self) -> (T, B, usize, usize) {
+        (self.inner, self.buf, self.offset, self.available)
+    }
+
+    /// Read from the inner reader into the empty buffer, caching EOF if no bytes were read
+    ///
+    /// The mark is discarded if the bytes kept for it fill the buffer.
+    async fn refill(&mut self) -> Result<(), T::Error> {
+        self.compact();
+        if !self.make_room(1) {
+            self.mark = None;
+            self.compact();
+        }
+        let start = self.offset;
+        self.inner_reads = self.inner_reads.wrapping_add(1);
+        self.available = self.inner.read(&mut self.buf.as_mut()[start..]).await?;
+        self.eof = self.available == 0;
+        Ok(())
+    }
+
+    /// Tap the `len` bytes at the current offset that are about to be consumed, skipping those already tapped
+    fn tap_consumed(&mut self, len: usize) {
+        let replayed = usize::min(len, self.replay);
+        self.replay -= replayed;
+        self.tap
+            .tap(&self.buf.as_ref()[self.offset + replayed..self.offset + len]);
+    }
+
+    /// Read from the inner reader until at least `n` bytes are available, returning `false` on EOF
+    async fn fill_to(&mut self, n: usize) -> Result<bool, FillError<T::Error>> {
+        debug_assert!(n <= self.buf.as_ref().len());
+        while self.available < n {
+            if self.eof {
+                return Ok(false);
+            }
+            if !self.make_room(n) {
+                return Err(FillError::Capacity);
+            }
+
+            let end = self.offset + self.available;
+            self.inner_reads = self.inner_reads.wrapping_add(1);
+            let read = self.inner.read(&mut self.buf.as_mut()[end..]).await?;
+            if read == 0 {
+                self.eof = true;
+                return Ok(false);
+            }
+            self.available += read;
+        }
+
+        Ok(true)
+    }
+
+    /// Move the available bytes, and the bytes after the mark if any, to the front of the buffer
+    fn compact(&mut self) {
+        let start = self.mark.unwrap_or(self.offset);
+        self.buf
+            .as_mut()
+            .copy_within(start..self.offset + self.available, 0);
+        self.offset -= start;
+        if self.mark.is_some() {
+            self.mark = Some(0);
+        }
+    }
+
+    /// Make room for `n` bytes after the current offset, returning `false` if the bytes kept for the mark prevent this
+    fn make_room(&mut self, n: usize) -> bool {
+        if self.offset + n > self.buf.as_ref().len() {
+            self.compact();
+        }
+        self.offset + n <= self.buf.as_ref().len()
+    }
+}
+
+/// Iterator over the available bytes of a [`BufferedRead`]
+///
+/// See [`BufferedRead::drain`].
+pub struct Drain<'a, F = NoTap> {
+    buf: &'a [u8],
+    offset: &'a mut usize,
+    available: &'a mut usize,
+    read_total: &'a mut u64,
+    replay: &'a mut usize,
+    tap: &'a mut F,
+}
+
+impl<F: Tap> Iterator for Drain<'_, F> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if *self.available == 0 {
+            return None;
+        }
+
+        let byte = self.buf[*self.offset];
+        if *self.replay > 0 {
+            *self.replay -= 1;
+        } else {
+            self.tap.tap(&[byte]);
+        }
+        *self.offset += 1;
+        *self.available -= 1;
+        *self.read_total += 1;
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (*self.available, Some(*self.available))
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>, F> defmt::Format for BufferedRead<'_, T, B, F> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "BufferedRead {{ available: {}, capacity: {} }}",
+            self.available,
+            self.buf.as_ref().len()
+        )
+    }
 }
 
-impl<T: Read> embedded_io::ErrorType for BufferedRead<'_, T> {
+impl<T: Read, B, F> embedded_io::ErrorType for BufferedRead<'_, T, B, F> {
     type Error = T::Error;
 }
 
-impl<T: Read + Write> Write for BufferedRead<'_, T> {
+impl<T: Read + Write, B: AsRef<[u8]> + AsMut<[u8]>, F> Write for BufferedRead<'_, T, B, F> {
     async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
         self.inner.write(buf).await
     }
@@ -79,51 +739,124 @@ impl<T: Read + Write> Write for BufferedRead<'_, T> {
     }
 }
 
-impl<T: Read> Read for BufferedRead<'_, T> {
+impl<T: Read + WriteReady, B, F> WriteReady for BufferedRead<'_, T, B, F> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        self.inner.write_ready()
+    }
+}
+
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>, F: Tap> Read for BufferedRead<'_, T, B, F> {
     async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
         if self.available == 0 {
-            if buf.len() >= self.buf.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            if self.mark.is_none() && buf.len() >= self.bypass_threshold() {
                 // Fast path - bypass local buffer
-                return self.inner.read(buf).await;
+                self.inner_reads = self.inner_reads.wrapping_add(1);
+                let read = self.inner.read(buf).await?;
+                self.eof = read == 0 && !buf.is_empty();
+                self.read_total += read as u64;
+                self.bypass_reads = self.bypass_reads.wrapping_add(1);
+                self.tap.tap(&buf[..read]);
+                return Ok(read);
             }
-            self.offset = 0;
-            self.available = self.inner.read(self.buf).await?;
+            self.refill().await?;
         }
 
         let len = usize::min(self.available, buf.len());
-        buf[..len].copy_from_slice(&self.buf[self.offset..self.offset + len]);
-        if len < self.available {
-            // There are still bytes left
+        buf[..len].copy_from_slice(&self.buf.as_ref()[self.offset..self.offset + len]);
+        self.tap_consumed(len);
+        if len < self.available || self.mark.is_some() {
+            // There are still bytes left, or the consumed bytes must be kept for the mark
             self.offset += len;
             self.available -= len;
         } else {
             // The buffer is drained
             self.available = 0;
         }
+        self.read_total += len as u64;
+        self.buffered_reads = self.buffered_reads.wrapping_add(1);
 
         Ok(len)
     }
+
+    async fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), ReadExactError<Self::Error>> {
+        // Drain the buffered bytes
+        let len = usize::min(self.available, buf.len());
+        buf[..len].copy_from_slice(&self.buf.as_ref()[self.offset..self.offset + len]);
+        self.tap_consumed(len);
+        self.offset += len;
+        self.available -= len;
+        self.read_total += len as u64;
+        buf = &mut buf[len..];
+
+        while !buf.is_empty() {
+            let read = if self.eof {
+                0
+            } else if self.mark.is_none() && buf.len() >= self.bypass_threshold() {
+                // Read large chunks directly into the destination
+                self.inner_reads = self.inner_reads.wrapping_add(1);
+                let read = self.inner.read(buf).await?;
+                self.eof = read == 0;
+                self.read_total += read as u64;
+                self.bypass_reads = self.bypass_reads.wrapping_add(1);
+                self.tap.tap(&buf[..read]);
+                read
+            } else {
+                // Read the tail through the local buffer
+                self.read(buf).await?
+            };
+            if read == 0 {
+                return Err(ReadExactError::UnexpectedEof);
+            }
+            buf = &mut buf[read..];
+        }
+
+        Ok(())
+    }
 }
 
-impl<T: Read> BufRead for BufferedRead<'_, T> {
+impl<T: Read, B: AsRef<[u8]> + AsMut<[u8]>, F: Tap> BufRead for BufferedRead<'_, T, B, F> {
+    /// Get the available bytes, reading from the inner reader if there are none
+    ///
+    /// An empty slice is returned when the inner reader is at EOF. EOF is cached once the inner reader
+    /// has returned 0 bytes, so subsequent calls return an empty slice without reading from it again.
     async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
-        if self.available == 0 {
-            self.offset = 0;
-            self.available = self.inner.read(self.buf).await?;
+        if self.available == 0 && !self.eof {
+            self.refill().await?;
         }
 
-        Ok(&self.buf[self.offset..self.offset + self.available])
+        Ok(&self.buf.as_ref()[self.offset..self.offset + self.available])
     }
 
+    /// Consume `amt` of the available bytes
+    ///
+    /// Consuming more than the available bytes is a logic error that panics in debug builds.
+    /// In release builds `amt` is clamped to the number of available bytes.
     fn consume(&mut self, amt: usize) {
-        assert!(amt <= self.available);
+        debug_assert!(amt <= self.available);
+        let amt = usize::min(amt, self.available);
+        self.tap_consumed(amt);
         self.offset += amt;
         self.available -= amt;
+        self.read_total += amt as u64;
+    }
+}
+
+impl<T: Read + ReadReady, B, F> ReadReady for BufferedRead<'_, T, B, F> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        // The reader is also ready if it is known to be at EOF, as reads then return immediately
+        Ok(self.available > 0 || self.eof || self.inner.read_ready()?)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::convert::Infallible;
+
+    use embedded_io::ErrorType;
+
     use super::*;
 
     #[tokio::test]
@@ -165,21 +898,70 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn can_buf_read() {
-        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    async fn counts_inner_reads() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(1, buffered.read(&mut [0; 1]).await.unwrap());
+        assert_eq!(1, buffered.inner_reads());
+        assert_eq!(2, buffered.read(&mut [0; 2]).await.unwrap());
+        assert_eq!(1, buffered.inner_reads());
+
+        assert_eq!(1, buffered.read(&mut [0; 4]).await.unwrap());
+        assert_eq!(2, buffered.inner_reads());
+        assert_eq!(2, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn bypass_at_threshold() {
+        let inner = ChunkedRead::new(&[&[1, 2], &[3, 4, 5]]);
         let mut buf = [0; 8];
-        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
-        assert_eq!(0, buffered.offset);
+        let mut buffered = BufferedRead::new(inner, &mut buf).with_bypass_threshold(2);
+        assert_eq!(2, buffered.bypass_threshold());
+
+        let mut read_buf = [0; 2];
+        assert_eq!(2, buffered.read(&mut read_buf).await.unwrap());
         assert_eq!(0, buffered.available);
+        assert_eq!(&[1, 2], &read_buf);
 
-        assert_eq!(
-            &[1, 2, 3, 4, 5, 6, 7, 8],
-            buffered.fill_buf().await.unwrap()
-        );
-        assert_eq!(0, buffered.offset);
-        assert_eq!(8, buffered.available);
+        let mut read_buf = [0; 1];
+        assert_eq!(1, buffered.read(&mut read_buf).await.unwrap());
+        assert_eq!(2, buffered.available);
 
-        buffered.consume(2);
+        let buffered = BufferedRead::with_storage([1].as_slice(), [0; 4]).with_bypass_threshold(10);
+        assert_eq!(4, buffered.bypass_threshold());
+    }
+
+    #[tokio::test]
+    async fn counts_bypass_and_buffered_reads() {
+        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        assert_eq!(1, buffered.read(&mut [0; 1]).await.unwrap());
+        assert_eq!(3, buffered.read(&mut [0; 4]).await.unwrap());
+        assert_eq!(4, buffered.read(&mut [0; 4]).await.unwrap());
+        assert_eq!(1, buffered.bypass_count());
+        assert_eq!(2, buffered.buffered_count());
+    }
+
+    #[tokio::test]
+    async fn can_buf_read() {
+        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut buf = [0; 8];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+        assert_eq!(0, buffered.offset);
+        assert_eq!(0, buffered.available);
+
+        assert_eq!(
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+            buffered.fill_buf().await.unwrap()
+        );
+        assert_eq!(0, buffered.offset);
+        assert_eq!(8, buffered.available);
+
+        buffered.consume(2);
         assert_eq!(2, buffered.offset);
         assert_eq!(6, buffered.available);
         assert_eq!(&[3, 4, 5, 6, 7, 8], buffered.fill_buf().await.unwrap());
@@ -196,4 +978,699 @@ mod tests {
         assert_eq!(2, buffered.offset);
         assert_eq!(0, buffered.available);
     }
+
+    #[tokio::test]
+    async fn can_peek() {
+        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut buf = [0; 8];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+        assert!(buffered.peek().is_empty());
+
+        buffered.fill_buf().await.unwrap();
+        buffered.consume(2);
+        assert_eq!(&[3, 4, 5, 6, 7, 8], buffered.peek());
+        assert_eq!(&[3, 4, 5, 6, 7, 8], buffered.buffer());
+        assert_eq!(2, buffered.offset);
+        assert_eq!(6, buffered.available);
+    }
+
+    #[tokio::test]
+    async fn can_peek_byte() {
+        let inner = [1, 2];
+        let mut buf = [0; 8];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        assert_eq!(Some(1), buffered.peek_byte().await.unwrap());
+        assert_eq!(Some(1), buffered.peek_byte().await.unwrap());
+
+        let mut read_buf = [0; 2];
+        assert_eq!(2, buffered.read(&mut read_buf).await.unwrap());
+        assert_eq!(&[1, 2], read_buf.as_slice());
+
+        assert_eq!(None, buffered.peek_byte().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_peek_exact() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]]);
+        let mut buf = [0; 8];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(&[1, 2], buffered.peek_exact(2).await.unwrap());
+        assert_eq!(0, buffered.offset);
+        assert_eq!(3, buffered.available);
+
+        buffered.consume(2);
+        assert_eq!(&[3, 4, 5], buffered.peek_exact(3).await.unwrap());
+        assert_eq!(2, buffered.offset);
+        assert_eq!(4, buffered.available);
+
+        // Requires compaction as there is not room for 7 bytes after offset
+        assert_eq!(
+            &[3, 4, 5, 6, 7, 8, 9],
+            buffered.peek_exact(7).await.unwrap()
+        );
+        assert_eq!(0, buffered.offset);
+        assert_eq!(7, buffered.available);
+
+        assert_eq!(Err(FillError::Capacity), buffered.peek_exact(9).await);
+        assert_eq!(Err(FillError::UnexpectedEof), buffered.peek_exact(8).await);
+    }
+
+    #[tokio::test]
+    async fn can_read_until() {
+        let inner = ChunkedRead::new(&[b"AT", b"\r\nOK\r", b"\nERR"]);
+        let mut buf = [0; 8];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        let mut out = [0; 16];
+        assert_eq!(4, buffered.read_until(b'\n', &mut out).await.unwrap());
+        assert_eq!(b"AT\r\n", &out[..4]);
+
+        // Stops when out is full
+        let mut out = [0; 2];
+        assert_eq!(2, buffered.read_until(b'\n', &mut out).await.unwrap());
+        assert_eq!(b"OK", &out);
+
+        let mut out = [0; 16];
+        assert_eq!(2, buffered.read_until(b'\n', &mut out).await.unwrap());
+        assert_eq!(b"\r\n", &out[..2]);
+
+        // Stops at EOF
+        assert_eq!(3, buffered.read_until(b'\n', &mut out).await.unwrap());
+        assert_eq!(b"ERR", &out[..3]);
+        assert_eq!(0, buffered.read_until(b'\n', &mut out).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_skip() {
+        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        assert_eq!(1, buffered.skip(1).await.unwrap());
+        assert_eq!(1, buffered.offset);
+        assert_eq!(3, buffered.available);
+
+        assert_eq!(6, buffered.skip(6).await.unwrap());
+        assert_eq!(3, buffered.offset);
+        assert_eq!(1, buffered.available);
+        assert_eq!(Some(8), buffered.peek_byte().await.unwrap());
+
+        assert_eq!(3, buffered.skip(5).await.unwrap());
+        assert_eq!(0, buffered.available);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[tokio::test]
+    async fn can_read_to_end() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5, 6, 7], &[8]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(1, buffered.read(&mut [0; 1]).await.unwrap());
+        let mut out = vec![0];
+        assert_eq!(7, buffered.read_to_end(&mut out).await.unwrap());
+        assert_eq!(&[0, 2, 3, 4, 5, 6, 7, 8], out.as_slice());
+        assert!(buffered.is_eof().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_ready_considers_buffered_bytes() {
+        let inner = ChunkedRead::new(&[&[1, 2]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert!(buffered.read_ready().unwrap());
+        assert_eq!(&[1, 2], buffered.fill_buf().await.unwrap());
+        assert!(buffered.read_ready().unwrap());
+
+        buffered.consume(2);
+        assert!(!buffered.read_ready().unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_try_consume() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        assert_eq!(&[1, 2, 3, 4], buffered.fill_buf().await.unwrap());
+        assert!(buffered.try_consume(5).is_err());
+        assert_eq!(4, buffered.available());
+
+        buffered.try_consume(3).unwrap();
+        assert_eq!(&[4], buffered.fill_buf().await.unwrap());
+        assert_eq!(3, buffered.bytes_read());
+    }
+
+    #[tokio::test]
+    async fn try_new_with_data_validates_capacity() {
+        let inner = [3, 4];
+        let mut buf = [0, 1, 2, 0];
+
+        let mut buffered =
+            BufferedRead::try_new_with_data(inner.as_slice(), &mut buf, 1, 2).unwrap();
+        assert_eq!(&[1, 2], buffered.fill_buf().await.unwrap());
+
+        assert!(BufferedRead::try_new_with_data(inner.as_slice(), &mut buf, 3, 2).is_err());
+        assert!(
+            BufferedRead::try_new_with_data(inner.as_slice(), &mut buf, 1, usize::MAX).is_err()
+        );
+    }
+
+    #[test]
+    fn new_coerces_array_to_slice() {
+        fn use_default(_: BufferedRead<'_, &[u8]>) {}
+
+        let mut buf = [0; 4];
+        use_default(BufferedRead::new([1].as_slice(), &mut buf));
+    }
+
+    #[tokio::test]
+    async fn can_drain() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(0, buffered.drain().count());
+        assert_eq!(&[1, 2, 3], buffered.fill_buf().await.unwrap());
+
+        assert_eq!(Some(1), buffered.drain().next());
+        assert_eq!(vec![2, 3], buffered.drain().collect::<Vec<_>>());
+        assert!(buffered.is_empty());
+        assert_eq!(3, buffered.bytes_read());
+        assert_eq!(1, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn can_read_buffered() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        let mut out = [0; 2];
+        assert_eq!(0, buffered.read_buffered(&mut out));
+        assert_eq!(&[1, 2, 3], buffered.fill_buf().await.unwrap());
+
+        assert_eq!(2, buffered.read_buffered(&mut out));
+        assert_eq!([1, 2], out);
+        assert_eq!(1, buffered.read_buffered(&mut out));
+        assert_eq!(3, out[0]);
+        assert_eq!(0, buffered.read_buffered(&mut out));
+        assert_eq!(3, buffered.bytes_read());
+        assert_eq!(1, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn can_split_buffered() {
+        let inner = ChunkedRead::new(&[&[1, 2], &[3, 4, 5, 6]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(&[1, 2], buffered.fill_buf().await.unwrap());
+        buffered.mark();
+        assert!(buffered.split_buffered().is_err());
+        buffered.clear_mark();
+
+        let (prefix, inner) = buffered.split_buffered().unwrap();
+        let mut tail = [0; 4];
+        assert_eq!(4, inner.read(&mut tail).await.unwrap());
+        assert_eq!(&[1, 2], prefix);
+        assert_eq!([3, 4, 5, 6], tail);
+        assert_eq!(2, buffered.available());
+    }
+
+    #[tokio::test]
+    async fn tap_sees_delivered_bytes_once() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5, 6, 7, 8], &[9, 10]]);
+        let mut buf = [0; 4];
+        let mut tapped = Vec::new();
+        let mut buffered =
+            BufferedRead::new(inner, &mut buf).with_tap(|bytes| tapped.extend_from_slice(bytes));
+
+        assert_eq!(&[1, 2], buffered.peek_exact(2).await.unwrap());
+        assert_eq!(1, buffered.read(&mut [0; 1]).await.unwrap());
+        buffered.consume(1);
+        assert_eq!(Some(3), buffered.drain().next());
+        assert_eq!(5, buffered.read(&mut [0; 5]).await.unwrap());
+        assert_eq!(&[9, 10], buffered.fill_buf().await.unwrap());
+        assert_eq!(0x090a, buffered.read_u16_be().await.unwrap());
+
+        assert_eq!(10, buffered.bytes_read());
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], tapped.as_slice());
+    }
+
+    #[tokio::test]
+    async fn tap_does_not_see_replayed_bytes() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3, 4, 5, 6]]);
+        let mut buf = [0; 8];
+        let mut tapped = Vec::new();
+        let mut buffered =
+            BufferedRead::new(inner, &mut buf).with_tap(|bytes| tapped.extend_from_slice(bytes));
+
+        assert_eq!(&[1, 2, 3, 4, 5, 6], buffered.fill_buf().await.unwrap());
+        buffered.mark();
+        buffered.consume(2);
+        buffered.reset_to_mark().unwrap();
+        let mut read_buf = [0; 4];
+        buffered.read_exact(&mut read_buf).await.unwrap();
+        assert_eq!([1, 2, 3, 4], read_buf);
+        buffered.reset_to_mark().unwrap();
+        assert_eq!(Some(1), buffered.drain().next());
+        buffered.consume(4);
+
+        assert_eq!(5, buffered.bytes_read());
+        assert_eq!(&[1, 2, 3, 4, 5], tapped.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_zeroize_on_drop() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf).zeroize_on_drop();
+
+        assert_eq!(&[1, 2, 3, 4], buffered.fill_buf().await.unwrap());
+        drop(buffered);
+        assert_eq!([0; 4], buf);
+    }
+
+    #[tokio::test]
+    async fn can_read_into_spare_capacity() {
+        let inner = [5, 6];
+        let mut buf = [0, 1, 2, 0];
+        let mut buffered = BufferedRead::new_with_data(inner.as_slice(), &mut buf, 1, 2);
+
+        let spare = buffered.spare_capacity_mut();
+        assert_eq!(2, spare.len());
+        spare[0] = 3;
+        buffered.commit(1);
+        assert_eq!(&[1, 2, 3], buffered.fill_buf().await.unwrap());
+
+        buffered.consume(3);
+        assert_eq!(&[5, 6], buffered.fill_buf().await.unwrap());
+    }
+
+    #[test]
+    fn can_hold() {
+        let buffered = BufferedReadN::with_storage([1].as_slice(), [0; 4]);
+        assert!(buffered.can_hold(4));
+        assert!(!buffered.can_hold(5));
+    }
+
+    #[tokio::test]
+    async fn can_consume_all() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        assert_eq!(1, buffered.read(&mut [0; 1]).await.unwrap());
+        assert_eq!(3, buffered.consume_all());
+        assert_eq!(0, buffered.consume_all());
+        assert_eq!(&[5, 6], buffered.fill_buf().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_prefill() {
+        let inner = ChunkedRead::new(&[&[1, 2], &[3]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(2, buffered.prefill().await.unwrap());
+        assert_eq!(0, buffered.prefill().await.unwrap());
+        assert_eq!(&[1, 2], buffered.buffer());
+        assert_eq!(1, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn can_reset_to_mark() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5], &[6, 7, 8, 9]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert!(buffered.reset_to_mark().is_err());
+        assert_eq!(1, buffered.read(&mut [0; 1]).await.unwrap());
+        buffered.mark();
+        assert_eq!(2, buffered.read(&mut [0; 4]).await.unwrap());
+        assert_eq!(2, buffered.read(&mut [0; 4]).await.unwrap());
+        assert_eq!(5, buffered.bytes_read());
+
+        buffered.reset_to_mark().unwrap();
+        assert_eq!(1, buffered.bytes_read());
+        let mut read_buf = [0; 4];
+        buffered.read_exact(&mut read_buf).await.unwrap();
+        assert_eq!([2, 3, 4, 5], read_buf);
+
+        // The marked bytes fill the buffer, so the mark prevents filling it
+        assert!(buffered.bypass().is_err());
+        assert!(matches!(
+            buffered.fill_buf_at_least(1).await,
+            Err(FillError::Capacity)
+        ));
+        buffered.reset_to_mark().unwrap();
+        assert_eq!(&[2, 3, 4, 5], buffered.buffer());
+
+        buffered.clear_mark();
+        buffered.consume(4);
+        assert_eq!(&[6, 7, 8, 9], buffered.fill_buf().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_discards_mark_that_fills_buffer() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3, 4], &[5, 6]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        buffered.mark();
+        assert_eq!(4, buffered.read(&mut [0; 4]).await.unwrap());
+        let mut read_buf = [0; 4];
+        assert_eq!(2, buffered.read(&mut read_buf).await.unwrap());
+        assert_eq!([5, 6], read_buf[..2]);
+        assert!(buffered.reset_to_mark().is_err());
+    }
+
+    #[tokio::test]
+    async fn can_fill_buf_at_least() {
+        let inner = ChunkedRead::new(&[&[1, 2], &[3, 4, 5], &[6, 7]]);
+        let mut buf = [0; 6];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(
+            &[1, 2, 3, 4, 5],
+            buffered.fill_buf_at_least(3).await.unwrap()
+        );
+        buffered.consume(4);
+        assert_eq!(&[5], buffered.fill_buf_at_least(1).await.unwrap());
+
+        // Returns what was accumulated on EOF
+        assert_eq!(&[5, 6, 7], buffered.fill_buf_at_least(5).await.unwrap());
+        assert_eq!(0, buffered.offset);
+
+        assert_eq!(
+            Err(FillError::Capacity),
+            buffered.fill_buf_at_least(7).await
+        );
+    }
+
+    #[tokio::test]
+    async fn can_fill_buf_more() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5], &[6, 7, 8]]);
+        let mut buf = [0; 6];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(&[1, 2, 3], buffered.fill_buf().await.unwrap());
+        buffered.consume(1);
+        assert_eq!(&[2, 3, 4, 5], buffered.fill_buf_more().await.unwrap());
+        assert_eq!(0, buffered.offset);
+
+        buffered.consume(1);
+        assert_eq!(&[3, 4, 5, 6, 7, 8], buffered.fill_buf_more().await.unwrap());
+
+        // The buffer is full
+        assert_eq!(&[3, 4, 5, 6, 7, 8], buffered.fill_buf_more().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_read_exact() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5, 6, 7, 8, 9], &[10, 11, 12, 13], &[14]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        buffered.fill_buf().await.unwrap();
+        buffered.consume(1);
+        assert_eq!(1, buffered.inner.reads);
+
+        let mut read_buf = [0; 12];
+        buffered.read_exact(&mut read_buf).await.unwrap();
+        assert_eq!(
+            &[2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+            read_buf.as_slice()
+        );
+        assert_eq!(3, buffered.inner.reads);
+
+        let mut read_buf = [0; 2];
+        assert_eq!(
+            Err(ReadExactError::UnexpectedEof),
+            buffered.read_exact(&mut read_buf).await
+        );
+        assert_eq!(14, read_buf[0]);
+    }
+
+    #[tokio::test]
+    async fn can_detect_eof() {
+        let inner = ChunkedRead::new(&[&[1, 2]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert!(!buffered.is_eof().await.unwrap());
+        assert_eq!(1, buffered.inner.reads);
+        assert!(!buffered.is_eof().await.unwrap());
+        assert_eq!(1, buffered.inner.reads);
+
+        buffered.consume(2);
+        assert!(buffered.is_eof().await.unwrap());
+        assert_eq!(2, buffered.inner.reads);
+
+        // The EOF is cached
+        assert!(buffered.is_eof().await.unwrap());
+        let mut read_buf = [0; 8];
+        assert_eq!(0, buffered.read(&mut read_buf).await.unwrap());
+        assert!(buffered.fill_buf().await.unwrap().is_empty());
+        assert_eq!(2, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn is_not_eof_while_bytes_are_available() {
+        let inner = ChunkedRead::new(&[&[1, 2]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        // EOF is reached while filling, with bytes still available
+        assert!(matches!(
+            buffered.peek_exact(3).await,
+            Err(FillError::UnexpectedEof)
+        ));
+        assert_eq!(2, buffered.available());
+        assert!(!buffered.is_eof().await.unwrap());
+
+        buffered.consume(1);
+        assert!(!buffered.is_eof().await.unwrap());
+        buffered.consume(1);
+        assert!(buffered.is_eof().await.unwrap());
+        assert_eq!(2, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn fill_buf_caches_eof() {
+        let inner = EofOnceRead { reads: 0 };
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert!(buffered.fill_buf().await.unwrap().is_empty());
+        assert!(buffered.fill_buf().await.unwrap().is_empty());
+        assert_eq!(0, buffered.read(&mut [0; 8]).await.unwrap());
+        assert!(buffered.is_eof().await.unwrap());
+        assert_eq!(1, buffered.inner.reads);
+    }
+
+    #[tokio::test]
+    async fn can_read_array() {
+        let inner = ChunkedRead::new(&[&[1, 2, 3], &[4, 5]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!([1, 2], buffered.read_array().await.unwrap());
+        assert_eq!([3, 4], buffered.read_array().await.unwrap());
+        assert_eq!(
+            Err(ReadExactError::UnexpectedEof),
+            buffered.read_array::<2>().await
+        );
+    }
+
+    #[tokio::test]
+    async fn can_read_le() {
+        let inner = [
+            0x01, 0x02, 0xFE, 0xFF, 0x01, 0x02, 0x03, 0x04, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+            0x07, 0x08, 0xFF,
+        ];
+        let mut buf = [0; 8];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        assert_eq!(0x0201, buffered.read_u16_le().await.unwrap());
+        assert_eq!(-2, buffered.read_i16_le().await.unwrap());
+        assert_eq!(0x04030201, buffered.read_u32_le().await.unwrap());
+        assert_eq!(0x0807060504030201, buffered.read_u64_le().await.unwrap());
+        assert_eq!(
+            Err(ReadExactError::UnexpectedEof),
+            buffered.read_i32_le().await
+        );
+    }
+
+    #[tokio::test]
+    async fn can_read_be_across_reads() {
+        let inner = ChunkedRead::new(&[&[0x01, 0x02, 0x03], &[0x04, 0xFF, 0xFE]]);
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner, &mut buf);
+
+        assert_eq!(0x01, buffered.peek_byte().await.unwrap().unwrap());
+        assert_eq!(0x01020304, buffered.read_u32_be().await.unwrap());
+        assert_eq!(-2, buffered.read_i16_be().await.unwrap());
+        assert_eq!(
+            Err(ReadExactError::UnexpectedEof),
+            buffered.read_u64_be().await
+        );
+    }
+
+    #[tokio::test]
+    async fn can_bypass_with_buf() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        let (inner, buf) = buffered.bypass_with_buf().unwrap();
+        assert_eq!(4, inner.read(buf).await.unwrap());
+        assert_eq!(&[1, 2, 3, 4], buf);
+
+        buffered.fill_buf().await.unwrap();
+        assert!(buffered.bypass_with_buf().is_err());
+    }
+
+    #[tokio::test]
+    async fn can_split() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        buffered.fill_buf().await.unwrap();
+        buffered.consume(1);
+        assert_eq!(&[5, 6], buffered.get_ref());
+
+        let (inner, buf, offset, available) = buffered.split();
+        assert_eq!(&[5, 6], inner);
+        assert_eq!(&[2, 3, 4], &buf[offset..offset + available]);
+    }
+
+    #[tokio::test]
+    async fn can_clear() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        buffered.fill_buf().await.unwrap();
+        buffered.clear();
+        assert!(buffered.is_empty());
+        assert_eq!(&[5, 6], buffered.fill_buf().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_reconstruct_from_parts() {
+        let inner = [1, 2, 3, 4, 5, 6];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+
+        buffered.fill_buf().await.unwrap();
+        buffered.consume(1);
+
+        let (inner, buf, offset, available) = buffered.into_parts();
+        let mut buffered = BufferedRead::new_with_data(inner, buf, offset, available);
+        let mut read_buf = [0; 5];
+        buffered.read_exact(&mut read_buf).await.unwrap();
+        assert_eq!(&[2, 3, 4, 5, 6], read_buf.as_slice());
+    }
+
+    #[tokio::test]
+    async fn counts_bytes_read() {
+        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14];
+        let mut buf = [0; 4];
+        let mut buffered = BufferedRead::new(inner.as_slice(), &mut buf);
+        assert_eq!(4, buffered.capacity());
+
+        // Prefetched bytes are not counted
+        buffered.fill_buf().await.unwrap();
+        assert_eq!(0, buffered.bytes_read());
+
+        buffered.consume(1);
+        assert_eq!(1, buffered.bytes_read());
+
+        let mut read_buf = [0; 2];
+        buffered.read(&mut read_buf).await.unwrap();
+        assert_eq!(3, buffered.bytes_read());
+
+        let mut read_buf = [0; 6];
+        buffered.read_exact(&mut read_buf).await.unwrap();
+        assert_eq!(9, buffered.bytes_read());
+
+        let mut read_buf = [0; 8];
+        assert_eq!(5, buffered.read(&mut read_buf).await.unwrap());
+        assert_eq!(14, buffered.bytes_read());
+    }
+
+    #[tokio::test]
+    async fn can_read_with_owned_buffer() {
+        let inner = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut buffered = BufferedReadN::with_storage(inner.as_slice(), [0; 4]);
+
+        assert_eq!(&[1, 2, 3, 4], buffered.fill_buf().await.unwrap());
+        buffered.consume(1);
+        assert_eq!(3, buffered.available());
+
+        let mut read_buf = [0; 2];
+        assert_eq!(2, buffered.read(&mut read_buf).await.unwrap());
+        assert_eq!(&[2, 3], read_buf.as_slice());
+
+        let mut read_buf = [0; 5];
+        buffered.read_exact(&mut read_buf).await.unwrap();
+        assert_eq!(&[4, 5, 6, 7, 8], read_buf.as_slice());
+        assert_eq!(&[9, 10], buffered.release());
+    }
+
+    struct EofOnceRead {
+        reads: usize,
+    }
+
+    impl ErrorType for EofOnceRead {
+        type Error = Infallible;
+    }
+
+    impl Read for EofOnceRead {
+        async fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.reads += 1;
+            assert_eq!(1, self.reads, "read after EOF");
+            Ok(0)
+        }
+    }
+
+    struct ChunkedRead<'a> {
+        chunks: &'a [&'a [u8]],
+        reads: usize,
+    }
+
+    impl<'a> ChunkedRead<'a> {
+        fn new(chunks: &'a [&'a [u8]]) -> Self {
+            Self { chunks, reads: 0 }
+        }
+    }
+
+    impl ErrorType for ChunkedRead<'_> {
+        type Error = Infallible;
+    }
+
+    impl ReadReady for ChunkedRead<'_> {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.chunks.is_empty())
+        }
+    }
+
+    impl Read for ChunkedRead<'_> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            self.reads += 1;
+            let Some((chunk, rest)) = self.chunks.split_first() else {
+                return Ok(0);
+            };
+            assert!(chunk.len() <= buf.len());
+            buf[..chunk.len()].copy_from_slice(chunk);
+            self.chunks = rest;
+            Ok(chunk.len())
+        }
+    }
 }
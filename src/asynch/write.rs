@@ -1,31 +1,177 @@
-use embedded_io_async::{Read, Write};
+use core::fmt;
+use core::marker::PhantomData;
 
-use super::BypassError;
+use embedded_io::WriteFmtError;
+use embedded_io_async::{Read, ReadReady, Write, WriteReady};
+
+use super::{BypassError, FlushError, NoTap, ReserveError, Tap, ZeroizeOnDrop};
 
 /// A buffered [`Write`]
 ///
 /// The BufferedWrite will write into the provided buffer to avoid small writes to the inner writer.
-pub struct BufferedWrite<'buf, T: Write> {
+/// The buffer is typically a borrowed `&mut [u8]`, but can be any storage that implements
+/// `AsRef<[u8]>` and `AsMut<[u8]>`, such as an owned array.
+pub struct BufferedWrite<'buf, T: Write, B = &'buf mut [u8], F = NoTap> {
     inner: T,
-    buf: &'buf mut [u8],
+    buf: B,
     pos: usize,
+    line_buffered: bool,
+    bypass_threshold: Option<usize>,
+    flush_watermark: Option<usize>,
+    inner_writes: u32,
+    inner_flushes: u32,
+    tap: F,
+    _buf: PhantomData<&'buf mut [u8]>,
 }
 
+/// The number of consecutive zero-length writes after which the inner writer is considered stalled
+const MAX_ZERO_WRITES: usize = 4;
+
+/// The size of the stack buffer that output is staged in when the writer has no buffer capacity
+const UNBUFFERED_CHUNK_LEN: usize = 16;
+
+/// A buffered [`Write`] that owns its buffer of `N` bytes
+///
+/// Create it with [`BufferedWrite::with_storage`].
+pub type BufferedWriteN<T, const N: usize> = BufferedWrite<'static, T, [u8; N]>;
+
 impl<'buf, T: Write> BufferedWrite<'buf, T> {
     /// Create a new buffered writer
     pub fn new(inner: T, buf: &'buf mut [u8]) -> Self {
-        Self { inner, buf, pos: 0 }
+        Self::with_storage(inner, buf)
+    }
+
+    /// Create a new line buffered writer
+    ///
+    /// See [`BufferedWrite::set_line_buffered`].
+    pub fn new_line_buffered(inner: T, buf: &'buf mut [u8]) -> Self {
+        let mut writer = Self::with_storage(inner, buf);
+        writer.line_buffered = true;
+        writer
     }
 
     /// Create a new buffered writer with a pre-polulated buffer
     pub fn new_with_data(inner: T, buf: &'buf mut [u8], written: usize) -> Self {
+        Self::with_storage_and_data(inner, buf, written)
+    }
+}
+
+impl<'buf, T: Write, B: AsRef<[u8]> + AsMut<[u8]>> BufferedWrite<'buf, T, B> {
+    /// Create a new buffered writer with any buffer storage, such as an owned array
+    pub fn with_storage(inner: T, buf: B) -> Self {
+        Self::with_storage_and_data(inner, buf, 0)
+    }
+
+    /// Create a new buffered writer with any buffer storage and a pre-polulated buffer
+    pub fn with_storage_and_data(inner: T, buf: B, written: usize) -> Self {
         Self {
             inner,
             buf,
             pos: written,
+            line_buffered: false,
+            bypass_threshold: None,
+            flush_watermark: None,
+            inner_writes: 0,
+            inner_flushes: 0,
+            tap: NoTap,
+            _buf: PhantomData,
+        }
+    }
+}
+
+impl<'buf, T: Write, B: AsRef<[u8]> + AsMut<[u8]>, F: Tap> BufferedWrite<'buf, T, B, F> {
+    /// Set a tap that is called with the bytes written to the writer
+    ///
+    /// The tap is called exactly once for every byte, in the order the bytes are written,
+    /// regardless of whether they are buffered or written directly to the inner writer.
+    /// Bytes reserved with [`BufferedWrite::reserve`], bytes inherited with [`BufferedWrite::new_with_data`],
+    /// and modifications through [`BufferedWrite::buffer_mut`] are not seen by the tap.
+    pub fn with_tap<G: FnMut(&[u8])>(self, tap: G) -> BufferedWrite<'buf, T, B, G> {
+        BufferedWrite {
+            inner: self.inner,
+            buf: self.buf,
+            pos: self.pos,
+            line_buffered: self.line_buffered,
+            bypass_threshold: self.bypass_threshold,
+            flush_watermark: self.flush_watermark,
+            inner_writes: self.inner_writes,
+            inner_flushes: self.inner_flushes,
+            tap,
+            _buf: PhantomData,
         }
     }
 
+    /// Overwrite the buffer with zeros when the writer is dropped
+    ///
+    /// This is useful if sensitive data passes through the buffer.
+    pub fn zeroize_on_drop(self) -> BufferedWrite<'buf, T, ZeroizeOnDrop<B>, F> {
+        BufferedWrite {
+            inner: self.inner,
+            buf: ZeroizeOnDrop::new(self.buf),
+            pos: self.pos,
+            line_buffered: self.line_buffered,
+            bypass_threshold: self.bypass_threshold,
+            flush_watermark: self.flush_watermark,
+            inner_writes: self.inner_writes,
+            inner_flushes: self.inner_flushes,
+            tap: self.tap,
+            _buf: PhantomData,
+        }
+    }
+
+    /// Set the size at which writes bypass the buffer and go directly to the inner writer
+    ///
+    /// The threshold defaults to, and is clamped to at most, the capacity of the buffer.
+    pub fn with_bypass_threshold(mut self, threshold: usize) -> Self {
+        self.bypass_threshold = Some(threshold);
+        self
+    }
+
+    /// Get the size at which writes bypass the buffer and go directly to the inner writer
+    pub fn bypass_threshold(&self) -> usize {
+        let capacity = self.buf.as_ref().len();
+        self.bypass_threshold
+            .map_or(capacity, |threshold| usize::min(threshold, capacity))
+    }
+
+    /// Set the number of buffered bytes at which a write writes the buffer to the inner writer
+    ///
+    /// The watermark defaults to, and is clamped to at most, the capacity of the buffer,
+    /// in which case the buffer is only written when it is full.
+    /// The watermark only applies to [`Write::write`]. The other methods, such as [`BufferedWrite::write_byte`],
+    /// [`BufferedWrite::write_vectored`] and [`BufferedWrite::format_buffered`], only write the buffer when it is full.
+    pub fn with_flush_watermark(mut self, bytes: usize) -> Self {
+        self.flush_watermark = Some(bytes);
+        self
+    }
+
+    /// Get the number of buffered bytes at which a write writes the buffer to the inner writer
+    pub fn flush_watermark(&self) -> usize {
+        let capacity = self.buf.as_ref().len();
+        self.flush_watermark
+            .map_or(capacity, |watermark| usize::min(watermark, capacity))
+    }
+
+    /// Set whether the writer is line buffered
+    ///
+    /// A line buffered writer writes complete lines to the inner writer and flushes it as soon as they are written,
+    /// while a trailing partial line is kept in the buffer.
+    pub fn set_line_buffered(&mut self, line_buffered: bool) {
+        self.line_buffered = line_buffered;
+    }
+
+    /// Get the number of writes that were issued to the inner writer
+    ///
+    /// Writes that are satisfied by the buffer are not included.
+    pub fn inner_writes(&self) -> u32 {
+        self.inner_writes
+    }
+
+    /// Get the number of flushes that were issued to the inner writer
+    pub fn inner_flushes(&self) -> u32 {
+        self.inner_flushes
+    }
+
     /// Get whether there are any bytes currently buffered
     pub fn is_empty(&self) -> bool {
         self.pos == 0
@@ -36,6 +182,99 @@ impl<'buf, T: Write> BufferedWrite<'buf, T> {
         self.pos
     }
 
+    /// Get the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.buf.as_ref().len()
+    }
+
+    /// Get whether `n` bytes fit in the buffer at once
+    pub fn can_hold(&self, n: usize) -> bool {
+        n <= self.buf.as_ref().len()
+    }
+
+    /// Get the number of bytes that can be buffered before the buffer is full
+    pub fn remaining_capacity(&self) -> usize {
+        self.buf.as_ref().len() - self.pos
+    }
+
+    /// Get the currently buffered, written bytes
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf.as_ref()[..self.pos]
+    }
+
+    /// Get the currently buffered, written bytes for in-place modification
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        &mut self.buf.as_mut()[..self.pos]
+    }
+
+    /// Get the unused tail of the buffer for the caller to write directly into
+    ///
+    /// Call [`BufferedWrite::produce`] to make the bytes that were written into the slice part of the buffered bytes.
+    pub fn buf_write_slice(&mut self) -> &mut [u8] {
+        &mut self.buf.as_mut()[self.pos..]
+    }
+
+    /// Make `n` bytes that were written into the slice returned by [`BufferedWrite::buf_write_slice`] buffered
+    ///
+    /// The buffer is written to the inner writer if it becomes full.
+    pub async fn produce(&mut self, n: usize) -> Result<(), T::Error> {
+        assert!(n <= self.remaining_capacity());
+        self.tap.tap(&self.buf.as_ref()[self.pos..self.pos + n]);
+        self.pos += n;
+        self.flush_if_full().await
+    }
+
+    /// Buffer as many bytes from `buf` as fit in the remaining capacity without writing to the inner writer
+    ///
+    /// The number of buffered bytes is returned, which is 0 when the buffer is full.
+    /// This never blocks, so the caller can drop the bytes that did not fit.
+    pub fn try_write(&mut self, buf: &[u8]) -> usize {
+        let len = usize::min(buf.len(), self.remaining_capacity());
+        self.buf.as_mut()[self.pos..self.pos + len].copy_from_slice(&buf[..len]);
+        self.pos += len;
+        self.tap.tap(&buf[..len]);
+        len
+    }
+
+    /// Reserve `n` contiguous bytes in the buffer, writing the buffered bytes to the inner writer if there is not room
+    ///
+    /// The reserved bytes are considered written and are returned for in-place modification.
+    /// [`ReserveError::WriteZero`] is returned if the inner writer repeatedly returns `Ok(0)`.
+    pub async fn reserve(&mut self, n: usize) -> Result<&mut [u8], ReserveError<T::Error>> {
+        if n > self.buf.as_ref().len() {
+            return Err(ReserveError::Capacity);
+        }
+        if n > self.remaining_capacity() {
+            match self.try_write_buffered().await {
+                Ok(()) => {}
+                Err(FlushError::WriteZero) => return Err(ReserveError::WriteZero),
+                Err(FlushError::Other(err)) => return Err(ReserveError::Other(err)),
+            }
+        }
+
+        let start = self.pos;
+        self.pos += n;
+        Ok(&mut self.buf.as_mut()[start..self.pos])
+    }
+
+    /// Write only as many of the leading buffered bytes to the inner writer as required for `free` bytes to be
+    /// available in the buffer
+    ///
+    /// The bytes that are not written remain buffered, and the inner writer is not flushed.
+    /// [`ReserveError::WriteZero`] is returned if the inner writer repeatedly returns `Ok(0)`.
+    pub async fn flush_at_least(&mut self, free: usize) -> Result<(), ReserveError<T::Error>> {
+        let capacity = self.buf.as_ref().len();
+        if free > capacity {
+            return Err(ReserveError::Capacity);
+        }
+
+        match self.try_write_buffered_to(capacity - free).await {
+            Ok(()) => Ok(()),
+            Err(FlushError::WriteZero) => Err(ReserveError::WriteZero),
+            Err(FlushError::Other(err)) => Err(ReserveError::Other(err)),
+        }
+    }
+
     /// Clear the currently buffered, written bytes
     pub fn clear(&mut self) {
         self.pos = 0;
@@ -52,27 +291,474 @@ impl<'buf, T: Write> BufferedWrite<'buf, T> {
     /// Get the inner writer if there are no currently buffered, written bytes, and rent the buffer
     pub fn bypass_with_buf(&mut self) -> Result<(&mut T, &mut [u8]), BypassError> {
         match self.pos {
-            0 => Ok((&mut self.inner, self.buf)),
+            0 => Ok((&mut self.inner, self.buf.as_mut())),
             _ => Err(BypassError),
         }
     }
 
+    /// Flush the buffered bytes and get the inner writer
+    ///
+    /// Unlike [`BufferedWrite::bypass`], this does not fail if there are buffered bytes,
+    /// as they are written to the inner writer, in order, before it is returned.
+    pub async fn flush_and_bypass(&mut self) -> Result<&mut T, T::Error> {
+        self.flush().await?;
+        Ok(&mut self.inner)
+    }
+
     /// Split the writer to get the inner components
     pub fn split(&mut self) -> (&mut T, &mut [u8], usize) {
-        (&mut self.inner, self.buf, self.pos)
+        (&mut self.inner, self.buf.as_mut(), self.pos)
+    }
+
+    /// Get a reference to the inner writer
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner writer, regardless of any currently buffered bytes
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
     }
 
     /// Release and get the inner writer
     pub fn release(self) -> T {
         self.inner
     }
+
+    /// Release and get the inner components
+    ///
+    /// The returned number of buffered bytes can be passed to [`BufferedWrite::with_storage_and_data`]
+    /// to construct a new writer that inherits the currently buffered bytes.
+    pub fn into_parts(self) -> (T, B, usize) {
+        (self.inner, self.buf, self.pos)
+    }
+
+    /// Get mutable access to the underlying buffer storage
+    #[cfg(feature = "alloc")]
+    pub(super) fn storage_mut(&mut self) -> &mut B {
+        &mut self.buf
+    }
+
+    /// Write a fixed-size array
+    ///
+    /// An array that fits in the buffer is buffered contiguously, writing the buffered bytes to the inner writer
+    /// first if there is not room. A larger array is written directly to the inner writer after the buffered bytes.
+    pub async fn write_array<const N: usize>(&mut self, data: [u8; N]) -> Result<(), T::Error> {
+        if N <= self.buf.as_ref().len() {
+            return self.write_contiguous(&data).await;
+        }
+
+        self.write_buffered().await?;
+        self.write_all_direct(&data).await?;
+        self.tap.tap(&data);
+        Ok(())
+    }
+
+    /// Write a little endian `u16`
+    pub async fn write_u16_le(&mut self, value: u16) -> Result<(), T::Error> {
+        self.write_array(value.to_le_bytes()).await
+    }
+
+    /// Write a big endian `u16`
+    pub async fn write_u16_be(&mut self, value: u16) -> Result<(), T::Error> {
+        self.write_array(value.to_be_bytes()).await
+    }
+
+    /// Write a little endian `i16`
+    pub async fn write_i16_le(&mut self, value: i16) -> Result<(), T::Error> {
+        self.write_array(value.to_le_bytes()).await
+    }
+
+    /// Write a big endian `i16`
+    pub async fn write_i16_be(&mut self, value: i16) -> Result<(), T::Error> {
+        self.write_array(value.to_be_bytes()).await
+    }
+
+    /// Write a little endian `u32`
+    pub async fn write_u32_le(&mut self, value: u32) -> Result<(), T::Error> {
+        self.write_array(value.to_le_bytes()).await
+    }
+
+    /// Write a big endian `u32`
+    pub async fn write_u32_be(&mut self, value: u32) -> Result<(), T::Error> {
+        self.write_array(value.to_be_bytes()).await
+    }
+
+    /// Write a little endian `i32`
+    pub async fn write_i32_le(&mut self, value: i32) -> Result<(), T::Error> {
+        self.write_array(value.to_le_bytes()).await
+    }
+
+    /// Write a big endian `i32`
+    pub async fn write_i32_be(&mut self, value: i32) -> Result<(), T::Error> {
+        self.write_array(value.to_be_bytes()).await
+    }
+
+    /// Write a little endian `u64`
+    pub async fn write_u64_le(&mut self, value: u64) -> Result<(), T::Error> {
+        self.write_array(value.to_le_bytes()).await
+    }
+
+    /// Write a big endian `u64`
+    pub async fn write_u64_be(&mut self, value: u64) -> Result<(), T::Error> {
+        self.write_array(value.to_be_bytes()).await
+    }
+
+    /// Write a little endian `i64`
+    pub async fn write_i64_le(&mut self, value: i64) -> Result<(), T::Error> {
+        self.write_array(value.to_le_bytes()).await
+    }
+
+    /// Write a big endian `i64`
+    pub async fn write_i64_be(&mut self, value: i64) -> Result<(), T::Error> {
+        self.write_array(value.to_be_bytes()).await
+    }
+
+    /// Write a single byte, writing the buffered bytes to the inner writer first if the buffer is full
+    ///
+    /// If the buffer has no capacity, the byte is written directly to the inner writer.
+    pub async fn write_byte(&mut self, byte: u8) -> Result<(), T::Error> {
+        if self.buf.as_ref().is_empty() {
+            self.write_all_direct(&[byte]).await?;
+            self.tap.tap(&[byte]);
+            return Ok(());
+        }
+
+        self.flush_if_full().await?;
+        self.buf.as_mut()[self.pos] = byte;
+        self.pos += 1;
+        self.tap.tap(&[byte]);
+        Ok(())
+    }
+
+    /// Write the buffered bytes to the inner writer only if the buffer is full
+    pub async fn flush_if_full(&mut self) -> Result<(), T::Error> {
+        if self.pos == self.buf.as_ref().len() {
+            self.write_buffered().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write multiple buffers, returning how many bytes were written
+    ///
+    /// Leading buffers that fit are coalesced into the buffer. If the first buffer does not fit,
+    /// the buffered bytes are written to the inner writer before the buffer is written.
+    /// As with [`Write::write`], it is not guaranteed that all bytes are written.
+    pub async fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, T::Error> {
+        let mut written = 0;
+        for buf in bufs {
+            if buf.len() <= self.remaining_capacity() {
+                self.buf.as_mut()[self.pos..self.pos + buf.len()].copy_from_slice(buf);
+                self.pos += buf.len();
+                self.tap.tap(buf);
+                written += buf.len();
+            } else if written > 0 {
+                // Let the caller continue with the remaining buffers
+                break;
+            } else {
+                self.write_buffered().await?;
+                return self.write(buf).await;
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Write all the buffered bytes to the inner writer and flush it, failing if the inner writer is stalled
+    ///
+    /// The inner writer is considered stalled if it repeatedly returns `Ok(0)`,
+    /// in which case [`FlushError::WriteZero`] is returned and the bytes that were not written remain buffered.
+    /// [`Write::flush`] panics in that case.
+    pub async fn try_flush(&mut self) -> Result<(), FlushError<T::Error>> {
+        self.try_write_buffered().await?;
+        self.inner_flushes = self.inner_flushes.wrapping_add(1);
+        self.inner.flush().await?;
+        Ok(())
+    }
+
+    /// Flush the buffered bytes and get the inner writer
+    pub async fn finish(mut self) -> Result<T, T::Error> {
+        self.flush().await?;
+        Ok(self.inner)
+    }
+
+    /// Format the arguments into the buffer, writing to the inner writer whenever the buffer fills
+    ///
+    /// The arguments are formatted again after each write to the inner writer, skipping the part
+    /// that was already buffered, so the formatting must be deterministic.
+    /// If the buffer has no capacity, the arguments are formatted in small chunks directly to the inner writer.
+    pub async fn format_buffered(
+        &mut self,
+        args: fmt::Arguments<'_>,
+    ) -> Result<(), WriteFmtError<T::Error>> {
+        let unbuffered = self.buf.as_ref().is_empty();
+        let mut chunk = [0; UNBUFFERED_CHUNK_LEN];
+        let mut formatted = 0;
+        loop {
+            let mut shim = FormatShim {
+                buf: if unbuffered {
+                    &mut chunk
+                } else {
+                    &mut self.buf.as_mut()[self.pos..]
+                },
+                skip: formatted,
+                written: 0,
+                overflow: false,
+            };
+            let result = fmt::write(&mut shim, args);
+            let (written, overflow) = (shim.written, shim.overflow);
+            formatted += written;
+
+            if unbuffered {
+                self.write_all_direct(&chunk[..written]).await?;
+                self.tap.tap(&chunk[..written]);
+            } else {
+                self.tap
+                    .tap(&self.buf.as_ref()[self.pos..self.pos + written]);
+                self.pos += written;
+
+                if self.pos == self.buf.as_ref().len() {
+                    // The buffer is full
+                    self.write_buffered().await?;
+                }
+            }
+
+            match result {
+                Ok(()) => return Ok(()),
+                Err(_) if overflow => continue,
+                Err(_) => return Err(WriteFmtError::FmtError),
+            }
+        }
+    }
+
+    /// Write each byte in `src` through `map`, which can emit any number of output bytes for each source byte
+    ///
+    /// The output bytes are written directly to the buffer, and the buffer is written to the inner writer when it is full.
+    /// If the output for a source byte does not fit in the buffer, `map` is called again for the same byte
+    /// after the buffer is written, and the output bytes that were already emitted are skipped.
+    /// `map` must therefore emit the same output every time it is called with the same byte.
+    /// If the buffer has no capacity, the output is written in small chunks directly to the inner writer.
+    pub async fn write_with(
+        &mut self,
+        src: &[u8],
+        mut map: impl FnMut(u8, &mut dyn FnMut(u8)),
+    ) -> Result<(), T::Error> {
+        let unbuffered = self.buf.as_ref().is_empty();
+        let mut chunk = [0; UNBUFFERED_CHUNK_LEN];
+        for &byte in src {
+            let mut emitted = 0;
+            loop {
+                let buf: &mut [u8] = if unbuffered {
+                    &mut chunk
+                } else {
+                    &mut self.buf.as_mut()[self.pos..]
+                };
+                let mut skip = emitted;
+                let mut written = 0;
+                let mut overflow = false;
+                map(byte, &mut |out| {
+                    if skip > 0 {
+                        skip -= 1;
+                    } else if written < buf.len() {
+                        buf[written] = out;
+                        written += 1;
+                    } else {
+                        overflow = true;
+                    }
+                });
+                emitted += written;
+
+                if unbuffered {
+                    self.write_all_direct(&chunk[..written]).await?;
+                    self.tap.tap(&chunk[..written]);
+                } else {
+                    self.tap
+                        .tap(&self.buf.as_ref()[self.pos..self.pos + written]);
+                    self.pos += written;
+
+                    if self.pos == self.buf.as_ref().len() {
+                        // The buffer is full
+                        self.write_buffered().await?;
+                    }
+                }
+                if !overflow {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the bytes contiguously into the buffer, writing the buffered bytes to the inner writer first if there is not room
+    async fn write_contiguous(&mut self, bytes: &[u8]) -> Result<(), T::Error> {
+        debug_assert!(bytes.len() <= self.buf.as_ref().len());
+        if bytes.len() > self.remaining_capacity() {
+            self.write_buffered().await?;
+        }
+
+        self.buf.as_mut()[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+        self.tap.tap(bytes);
+        Ok(())
+    }
+
+    /// Write the complete lines in `buf` to the inner writer, flush it, and buffer the trailing partial line
+    ///
+    /// If the flush fails, only the complete lines are reported as written, and the error is left
+    /// for the next flush to report, as the lines can no longer be taken back.
+    async fn write_lines(&mut self, buf: &[u8], newline: usize) -> Result<usize, T::Error> {
+        // Write the buffered bytes first to preserve ordering
+        self.write_buffered().await?;
+
+        let (lines, tail) = buf.split_at(newline + 1);
+        self.inner_writes = self.inner_writes.wrapping_add(1);
+        let written = self.inner.write(lines).await?;
+        self.tap.tap(&lines[..written]);
+        if written < lines.len() {
+            return Ok(written);
+        }
+
+        // Flush so that the complete lines are not held back by any buffering in the inner writer
+        self.inner_flushes = self.inner_flushes.wrapping_add(1);
+        if self.inner.flush().await.is_err() {
+            return Ok(written);
+        }
+
+        let buffered = usize::min(tail.len(), self.buf.as_ref().len());
+        self.buf.as_mut()[..buffered].copy_from_slice(&tail[..buffered]);
+        self.pos = buffered;
+        self.tap.tap(&tail[..buffered]);
+
+        Ok(written + buffered)
+    }
+
+    /// Write all of `bytes` directly to the inner writer, bypassing the buffer
+    ///
+    /// Zero-length writes are tolerated as in [`BufferedWrite::try_flush`], after which this panics.
+    async fn write_all_direct(&mut self, bytes: &[u8]) -> Result<(), T::Error> {
+        let mut remaining = bytes;
+        let mut zero_writes = 0;
+        while !remaining.is_empty() {
+            self.inner_writes = self.inner_writes.wrapping_add(1);
+            match self.inner.write(remaining).await? {
+                0 => {
+                    zero_writes += 1;
+                    if zero_writes == MAX_ZERO_WRITES {
+                        panic!("write() returned Ok(0)");
+                    }
+                }
+                written => {
+                    zero_writes = 0;
+                    remaining = &remaining[written..];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write all the currently buffered bytes to the inner writer
+    ///
+    /// If the inner writer fails, the bytes that were not yet written remain buffered.
+    async fn write_buffered(&mut self) -> Result<(), T::Error> {
+        match self.try_write_buffered().await {
+            Ok(()) => Ok(()),
+            Err(FlushError::WriteZero) => panic!("write() returned Ok(0)"),
+            Err(FlushError::Other(err)) => Err(err),
+        }
+    }
+
+    async fn try_write_buffered(&mut self) -> Result<(), FlushError<T::Error>> {
+        self.try_write_buffered_to(0).await
+    }
+
+    /// Write the leading buffered bytes to the inner writer until at most `pos` bytes remain buffered
+    async fn try_write_buffered_to(&mut self, pos: usize) -> Result<(), FlushError<T::Error>> {
+        let mut zero_writes = 0;
+        while self.pos > pos {
+            self.inner_writes = self.inner_writes.wrapping_add(1);
+            match self
+                .inner
+                .write(&self.buf.as_ref()[..self.pos - pos])
+                .await?
+            {
+                0 => {
+                    zero_writes += 1;
+                    if zero_writes == MAX_ZERO_WRITES {
+                        return Err(FlushError::WriteZero);
+                    }
+                }
+                written => {
+                    zero_writes = 0;
+                    // Move the bytes that were not written to the front of the buffer after each write,
+                    // so that the buffer remains consistent if the returned future is dropped
+                    self.buf.as_mut().copy_within(written..self.pos, 0);
+                    self.pos -= written;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'buf, T: Write, S: AsRef<[u8]> + ?Sized, F> BufferedWrite<'buf, T, &'buf mut S, F> {
+    /// Release and get the inner writer together with the currently buffered bytes that were not yet written
+    ///
+    /// Unlike [`BufferedWrite::release`], the buffered bytes are not lost, and unlike [`BufferedWrite::finish`],
+    /// they are not written to the inner writer, so that they can be written elsewhere.
+    pub fn release_with_pending(self) -> (T, &'buf [u8]) {
+        let buf: &'buf S = self.buf;
+        (self.inner, &buf.as_ref()[..self.pos])
+    }
+}
+
+/// A [`fmt::Write`] that formats into a slice, skipping a number of already formatted bytes
+struct FormatShim<'a> {
+    buf: &'a mut [u8],
+    skip: usize,
+    written: usize,
+    overflow: bool,
+}
+
+impl fmt::Write for FormatShim<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut bytes = s.as_bytes();
+        let skipped = usize::min(self.skip, bytes.len());
+        self.skip -= skipped;
+        bytes = &bytes[skipped..];
+
+        let len = usize::min(bytes.len(), self.buf.len() - self.written);
+        self.buf[self.written..self.written + len].copy_from_slice(&bytes[..len]);
+        self.written += len;
+
+        if len < bytes.len() {
+            // Abort the formatting as the buffer is full
+            self.overflow = true;
+            return Err(fmt::Error);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: Write, B: AsRef<[u8]> + AsMut<[u8]>, F> defmt::Format for BufferedWrite<'_, T, B, F> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "BufferedWrite {{ written: {}, capacity: {} }}",
+            self.pos,
+            self.buf.as_ref().len()
+        )
+    }
 }
 
-impl<T: Write> embedded_io::ErrorType for BufferedWrite<'_, T> {
+impl<T: Write, B, F> embedded_io::ErrorType for BufferedWrite<'_, T, B, F> {
     type Error = T::Error;
 }
 
-impl<T: Read + Write> Read for BufferedWrite<'_, T> {
+impl<T: Read + Write, B: AsRef<[u8]> + AsMut<[u8]>, F> Read for BufferedWrite<'_, T, B, F> {
     async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
         self.inner.read(buf).await
     }
@@ -85,53 +771,76 @@ impl<T: Read + Write> Read for BufferedWrite<'_, T> {
     }
 }
 
-impl<T: Write> Write for BufferedWrite<'_, T> {
+impl<T: Write + ReadReady, B, F> ReadReady for BufferedWrite<'_, T, B, F> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.inner.read_ready()
+    }
+}
+
+impl<T: Write, B: AsRef<[u8]> + AsMut<[u8]>, F: Tap> Write for BufferedWrite<'_, T, B, F> {
     async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
         if buf.is_empty() {
             return Ok(0);
         }
-        if self.pos == 0 && buf.len() >= self.buf.len() {
+        if self.line_buffered {
+            if let Some(newline) = buf.iter().rposition(|&b| b == b'\n') {
+                return self.write_lines(buf, newline).await;
+            }
+        }
+        if self.pos == self.buf.as_ref().len() {
+            // The buffer was filled by one of the methods that do not write a full buffer
+            self.write_buffered().await?;
+        }
+        if self.pos == 0 && buf.len() >= self.bypass_threshold() {
             // Fast path - nothing in buffer and the buffer to write is large
-            return self.inner.write(buf).await;
+            self.inner_writes = self.inner_writes.wrapping_add(1);
+            let written = self.inner.write(buf).await?;
+            self.tap.tap(&buf[..written]);
+            return Ok(written);
         }
 
-        let buffered = usize::min(buf.len(), self.buf.len() - self.pos);
+        let buffered = usize::min(buf.len(), self.buf.as_ref().len() - self.pos);
         assert!(buffered > 0);
 
         let mut new_pos = self.pos;
-        self.buf[new_pos..new_pos + buffered].copy_from_slice(&buf[..buffered]);
+        self.buf.as_mut()[new_pos..new_pos + buffered].copy_from_slice(&buf[..buffered]);
         new_pos += buffered;
 
-        if new_pos < self.buf.len() {
+        if new_pos < self.flush_watermark() {
             // The buffer to write could fit in the buffer
             self.pos = new_pos;
         } else {
-            // The buffer is full
-            let written = self.inner.write(self.buf).await?;
+            // The buffer is full, or filled to the watermark
+            self.inner_writes = self.inner_writes.wrapping_add(1);
+            let written = self.inner.write(&self.buf.as_ref()[..new_pos]).await?;
 
             // We only assign self.pos _after_ we are sure that the write has completed successfully
             if written < new_pos {
                 // We only partially wrote the inner buffer
-                self.buf.copy_within(written..new_pos, 0);
+                self.buf.as_mut().copy_within(written..new_pos, 0);
                 self.pos = new_pos - written;
             } else {
                 self.pos = 0;
             }
         }
+        self.tap.tap(&buf[..buffered]);
 
         Ok(buffered)
     }
 
     async fn flush(&mut self) -> Result<(), Self::Error> {
-        if self.pos > 0 {
-            self.inner.write_all(&self.buf[..self.pos]).await?;
-            self.pos = 0;
-        }
-
+        self.write_buffered().await?;
+        self.inner_flushes = self.inner_flushes.wrapping_add(1);
         self.inner.flush().await
     }
 }
 
+impl<T: Write + WriteReady, B: AsRef<[u8]>, F> WriteReady for BufferedWrite<'_, T, B, F> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.pos < self.buf.as_ref().len() || self.inner.write_ready()?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use embedded_io::{Error, ErrorKind, ErrorType};
@@ -169,6 +878,42 @@ mod tests {
         assert_eq!(8, buffered.inner.len());
     }
 
+    #[tokio::test]
+    async fn bypass_write_at_threshold() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf).with_bypass_threshold(2);
+
+        assert_eq!(1, buffered.write(&[1]).await.unwrap());
+        assert_eq!(1, buffered.pos);
+        buffered.flush().await.unwrap();
+
+        assert_eq!(2, buffered.write(&[2, 3]).await.unwrap());
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3], buffered.inner.as_slice());
+
+        let buffered = BufferedWrite::with_storage(Vec::new(), [0; 4]).with_bypass_threshold(10);
+        assert_eq!(4, buffered.bypass_threshold());
+    }
+
+    #[tokio::test]
+    async fn write_at_flush_watermark() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf).with_flush_watermark(4);
+
+        assert_eq!(3, buffered.write(&[1, 2, 3]).await.unwrap());
+        assert_eq!(3, buffered.pos);
+        assert_eq!(0, buffered.inner.len());
+
+        assert_eq!(2, buffered.write(&[4, 5]).await.unwrap());
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4, 5], buffered.inner.as_slice());
+
+        let buffered = BufferedWrite::with_storage(Vec::new(), [0; 4]).with_flush_watermark(10);
+        assert_eq!(4, buffered.flush_watermark());
+    }
+
     #[tokio::test]
     async fn large_write_when_not_empty() {
         let mut inner = Vec::new();
@@ -203,11 +948,31 @@ mod tests {
         assert_eq!(8, buffered.inner.written.len());
     }
 
+    #[tokio::test]
+    async fn flush_keeps_unwritten_bytes_on_write_errors() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.push(3); // Write partially
+        inner.writeable.push(0); // Return error
+        inner.writeable.push(3); // Write remaining bytes
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(6, buffered.write(&[1, 2, 3, 4, 5, 6]).await.unwrap());
+        assert!(buffered.flush().await.is_err());
+        assert_eq!(&[4, 5, 6], buffered.buffer());
+        assert_eq!(&[1, 2, 3], buffered.inner.written.as_slice());
+
+        buffered.flush().await.unwrap();
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4, 5, 6], buffered.inner.written.as_slice());
+    }
+
     #[derive(Default)]
     struct UnstableWrite {
         written: Vec<u8>,
         writes: usize,
         writeable: Vec<usize>,
+        failing_flushes: usize,
     }
 
     #[derive(Debug)]
@@ -223,17 +988,616 @@ mod tests {
         type Error = UnstableError;
     }
 
+    impl WriteReady for UnstableWrite {
+        fn write_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.writes < self.writeable.len())
+        }
+    }
+
     impl Write for UnstableWrite {
         async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
             let written = self.writeable[self.writes];
             self.writes += 1;
-            if written > 0 {
+            if written == usize::MAX {
+                Ok(0)
+            } else if written > 0 {
                 self.written.extend_from_slice(&buf[..written]);
                 Ok(written)
             } else {
                 Err(UnstableError)
             }
         }
+
+        async fn flush(&mut self) -> Result<(), Self::Error> {
+            if self.failing_flushes > 0 {
+                self.failing_flushes -= 1;
+                return Err(UnstableError);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn try_flush_fails_on_stalled_writer() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.resize(MAX_ZERO_WRITES, usize::MAX); // Return Ok(0)
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_all(&[1, 2, 3, 4]).await.unwrap();
+        assert!(matches!(
+            buffered.try_flush().await,
+            Err(FlushError::WriteZero)
+        ));
+        assert_eq!(&[1, 2, 3, 4], buffered.buffer());
+        assert_eq!(MAX_ZERO_WRITES, buffered.inner.writes);
+    }
+
+    #[tokio::test]
+    async fn flush_at_least_fails_on_stalled_writer() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.resize(MAX_ZERO_WRITES, usize::MAX); // Return Ok(0)
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_all(&[1, 2, 3]).await.unwrap();
+        assert!(matches!(
+            buffered.flush_at_least(2).await,
+            Err(ReserveError::WriteZero)
+        ));
+        assert_eq!(&[1, 2, 3], buffered.buffer());
+        assert_eq!(MAX_ZERO_WRITES, buffered.inner.writes);
+    }
+
+    #[tokio::test]
+    async fn reserve_fails_on_stalled_writer() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.resize(MAX_ZERO_WRITES, usize::MAX); // Return Ok(0)
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_all(&[1, 2, 3]).await.unwrap();
+        assert!(matches!(
+            buffered.reserve(2).await,
+            Err(ReserveError::WriteZero)
+        ));
+        assert_eq!(&[1, 2, 3], buffered.buffer());
+    }
+
+    #[tokio::test]
+    async fn write_array_tolerates_zero_writes() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.push(usize::MAX); // Return Ok(0)
+        inner.writeable.push(2); // Write partially
+        inner.writeable.push(usize::MAX); // Return Ok(0)
+        inner.writeable.push(2); // Write remaining bytes
+        let mut buf = [0; 2];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_array([1, 2, 3, 4]).await.unwrap();
+        assert_eq!(&[1, 2, 3, 4], buffered.inner.written.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_format_buffered() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered
+            .format_buffered(format_args!("GET {} HTTP/1.1\r\n", "/index.html"))
+            .await
+            .unwrap();
+        assert_eq!(2, buffered.pos);
+        assert_eq!(24, buffered.inner.len());
+
+        buffered.flush().await.unwrap();
+        assert_eq!(b"GET /index.html HTTP/1.1\r\n", inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn format_buffered_surfaces_write_errors() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.push(0); // Return error
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert!(matches!(
+            buffered.format_buffered(format_args!("{}", 12345)).await,
+            Err(WriteFmtError::Other(UnstableError))
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_format_buffered_without_capacity() {
+        let mut inner = Vec::new();
+        let mut buffered = BufferedWrite::new(&mut inner, &mut []);
+
+        buffered
+            .format_buffered(format_args!("GET {} HTTP/1.1\r\n", "/index.html"))
+            .await
+            .unwrap();
+        assert_eq!(b"GET /index.html HTTP/1.1\r\n", buffered.inner.as_slice());
+        assert_eq!(2, buffered.inner_writes());
+    }
+
+    #[tokio::test]
+    async fn can_write_with() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 2];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        let escape = |byte, emit: &mut dyn FnMut(u8)| match byte {
+            0xC0 => {
+                emit(0xDB);
+                emit(0xDC);
+            }
+            _ => emit(byte),
+        };
+        buffered
+            .write_with(&[1, 0xC0, 2, 0xC0, 3], escape)
+            .await
+            .unwrap();
+        assert_eq!(1, buffered.pos);
+        assert_eq!(&[1, 0xDB, 0xDC, 2, 0xDB, 0xDC], buffered.inner.as_slice());
+
+        buffered.flush().await.unwrap();
+        assert_eq!(
+            &[1, 0xDB, 0xDC, 2, 0xDB, 0xDC, 3],
+            buffered.inner.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn tap_sees_all_written_bytes() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 4];
+        let mut tapped = Vec::new();
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf)
+            .with_tap(|bytes| tapped.extend_from_slice(bytes));
+
+        buffered.write_all(&[1, 2, 3]).await.unwrap();
+        buffered.write_byte(4).await.unwrap();
+        buffered.write_all(&[5, 6, 7, 8, 9]).await.unwrap();
+        buffered.write_all(&[10, 11, 12, 13]).await.unwrap();
+        buffered.write_u16_be(0x0e0f).await.unwrap();
+        buffered.flush().await.unwrap();
+
+        assert_eq!(
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            inner.as_slice()
+        );
+        assert_eq!(inner, tapped);
+    }
+
+    #[tokio::test]
+    async fn can_zeroize_on_drop() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf).zeroize_on_drop();
+
+        buffered.write_all(&[1, 2, 3, 4, 5]).await.unwrap();
+        buffered.write_all(&[6, 7]).await.unwrap();
+        drop(buffered);
+        assert_eq!([0; 4], buf);
+        assert_eq!(&[1, 2, 3, 4, 5], inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_patch_buffer() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+        assert_eq!(8, buffered.capacity());
+
+        assert_eq!(3, buffered.write(&[0, 2, 3]).await.unwrap());
+        assert_eq!(5, buffered.remaining_capacity());
+        assert_eq!(&[0, 2, 3], buffered.buffer());
+
+        buffered.buffer_mut()[0] = 1;
+        buffered.flush().await.unwrap();
+        assert_eq!(8, buffered.remaining_capacity());
+        assert_eq!(&[1, 2, 3], inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn finish_flushes_buffer() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        let inner = buffered.finish().await.unwrap();
+        assert_eq!(&[1, 2], inner.as_slice());
+    }
+
+    #[test]
+    fn can_hold() {
+        let buffered = BufferedWriteN::with_storage(Vec::new(), [0; 4]);
+        assert!(buffered.can_hold(4));
+        assert!(!buffered.can_hold(5));
+    }
+
+    #[tokio::test]
+    async fn can_flush_at_least() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.push(1); // Write partially
+        inner.writeable.push(2); // Write the remaining needed bytes
+        let mut buf = [0; 6];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_all(&[1, 2, 3, 4, 5]).await.unwrap();
+        buffered.flush_at_least(4).await.unwrap();
+        assert_eq!(&[4, 5], buffered.buffer());
+        assert_eq!(&[1, 2, 3], buffered.inner.written.as_slice());
+
+        buffered.flush_at_least(4).await.unwrap();
+        assert_eq!(2, buffered.inner.writes);
+        assert!(matches!(
+            buffered.flush_at_least(7).await,
+            Err(ReserveError::Capacity)
+        ));
+    }
+
+    #[tokio::test]
+    async fn can_produce() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.buf_write_slice()[..3].copy_from_slice(&[1, 2, 3]);
+        buffered.produce(3).await.unwrap();
+        assert_eq!(&[1, 2, 3], buffered.buffer());
+
+        let slice = buffered.buf_write_slice();
+        assert_eq!(1, slice.len());
+        slice[0] = 4;
+        buffered.produce(1).await.unwrap();
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4], buffered.inner.as_slice());
+        assert_eq!(4, buffered.buf_write_slice().len());
+    }
+
+    #[test]
+    fn try_write_never_writes_to_inner() {
+        let mut inner = UnstableWrite::default();
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(3, buffered.try_write(&[1, 2, 3]));
+        assert_eq!(1, buffered.try_write(&[4, 5]));
+        assert_eq!(0, buffered.try_write(&[6]));
+        assert_eq!(&[1, 2, 3, 4], buffered.buffer());
+        assert_eq!(0, buffered.inner.writes);
+    }
+
+    #[tokio::test]
+    async fn can_reserve() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        buffered
+            .reserve(4)
+            .await
+            .unwrap()
+            .copy_from_slice(&[3, 4, 5, 6]);
+        assert_eq!(6, buffered.pos);
+        assert_eq!(0, buffered.inner.len());
+
+        // There is not room for 4 more bytes
+        buffered
+            .reserve(4)
+            .await
+            .unwrap()
+            .copy_from_slice(&[7, 8, 9, 10]);
+        assert_eq!(4, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4, 5, 6], buffered.inner.as_slice());
+
+        buffered
+            .reserve(4)
+            .await
+            .unwrap()
+            .copy_from_slice(&[11, 12, 13, 14]);
+        assert_eq!(8, buffered.pos);
+        assert_eq!(1, buffered.write(&[15]).await.unwrap());
+        assert_eq!(1, buffered.pos);
+        assert_eq!(14, buffered.inner.len());
+
+        assert_eq!(
+            Err(ReserveError::Capacity),
+            buffered.reserve(9).await.map(|_| ())
+        );
+    }
+
+    #[tokio::test]
+    async fn can_write_vectored() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        let header = [1, 2];
+        let body = [3, 4, 5];
+        assert_eq!(5, buffered.write_vectored(&[&header, &body]).await.unwrap());
+        assert_eq!(5, buffered.pos);
+        assert_eq!(0, buffered.inner.len());
+
+        let body = [6, 7, 8, 9, 10, 11, 12, 13];
+        assert_eq!(2, buffered.write_vectored(&[&header, &body]).await.unwrap());
+        assert_eq!(7, buffered.pos);
+
+        // The buffered bytes are written before the large body
+        assert_eq!(8, buffered.write_vectored(&[&body]).await.unwrap());
+        assert_eq!(0, buffered.pos);
+        assert_eq!(
+            &[1, 2, 3, 4, 5, 1, 2, 6, 7, 8, 9, 10, 11, 12, 13],
+            buffered.inner.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn line_buffered_writes_complete_lines() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new_line_buffered(&mut inner, &mut buf);
+
+        assert_eq!(3, buffered.write(b"abc").await.unwrap());
+        assert_eq!(0, buffered.inner.len());
+        assert_eq!(0, buffered.inner_flushes());
+
+        assert_eq!(5, buffered.write(b"d\nefg").await.unwrap());
+        assert_eq!(b"abcd\n", buffered.inner.as_slice());
+        assert_eq!(b"efg", buffered.buffer());
+        assert_eq!(1, buffered.inner_flushes());
+
+        assert_eq!(8, buffered.write(b"h\ni\njklm").await.unwrap());
+        assert_eq!(b"abcd\nefgh\ni\n", buffered.inner.as_slice());
+        assert_eq!(b"jklm", buffered.buffer());
+        assert_eq!(2, buffered.inner_flushes());
+
+        buffered.set_line_buffered(false);
+        assert_eq!(2, buffered.write(b"\n\n").await.unwrap());
+        assert_eq!(b"jklm\n\n", buffered.buffer());
+        assert_eq!(2, buffered.inner_flushes());
+    }
+
+    #[tokio::test]
+    async fn can_write_with_without_capacity() {
+        let mut inner = Vec::new();
+        let mut buffered = BufferedWrite::new(&mut inner, &mut []);
+
+        let repeat = |byte, emit: &mut dyn FnMut(u8)| {
+            for _ in 0..20 {
+                emit(byte);
+            }
+        };
+        buffered.write_with(&[1, 2], repeat).await.unwrap();
+        assert_eq!(&[[1; 20], [2; 20]].concat(), buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn line_buffered_write_succeeds_when_flush_fails() {
+        let mut inner = UnstableWrite::default();
+        inner.writeable.push(3); // Write the complete line
+        inner.writeable.push(2); // Write the partial line
+        inner.failing_flushes = 2;
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new_line_buffered(&mut inner, &mut buf);
+
+        // The line is written, so the failed flush cannot be reported without duplicating it on retry
+        assert_eq!(3, buffered.write(b"ab\ncd").await.unwrap());
+        assert!(buffered.is_empty());
+        assert_eq!(2, buffered.write(b"cd").await.unwrap());
+
+        assert!(buffered.flush().await.is_err());
+        buffered.flush().await.unwrap();
+        assert_eq!(b"ab\ncd", buffered.inner.written.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_write_byte() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 2];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_byte(1).await.unwrap();
+        buffered.flush_if_full().await.unwrap();
+        assert_eq!(1, buffered.pos);
+
+        buffered.write_byte(2).await.unwrap();
+        assert_eq!(2, buffered.pos);
+        assert_eq!(0, buffered.inner.len());
+
+        buffered.write_byte(3).await.unwrap();
+        assert_eq!(1, buffered.pos);
+        assert_eq!(&[1, 2], buffered.inner.as_slice());
+
+        buffered.write_byte(4).await.unwrap();
+        buffered.flush_if_full().await.unwrap();
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4], buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn write_ready_considers_remaining_capacity() {
+        let mut inner = UnstableWrite::default();
+        let mut buf = [0; 2];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert!(buffered.write_ready().unwrap());
+        buffered.write_byte(1).await.unwrap();
+        buffered.write_byte(2).await.unwrap();
+        assert!(!buffered.write_ready().unwrap());
+
+        buffered.inner.writeable.push(2);
+        assert!(buffered.write_ready().unwrap());
+    }
+
+    #[tokio::test]
+    async fn can_write_array() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_array([1, 2, 3]).await.unwrap();
+        assert_eq!(3, buffered.pos);
+        buffered.write_array([4, 5]).await.unwrap();
+        assert_eq!(2, buffered.pos);
+        assert_eq!(&[1, 2, 3], buffered.inner.as_slice());
+
+        buffered.write_array([6, 7, 8, 9, 10]).await.unwrap();
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_write_integers() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_u16_be(0x0102).await.unwrap();
+        buffered.write_i16_le(-2).await.unwrap();
+        buffered.write_u32_be(0x03040506).await.unwrap();
+        assert_eq!(8, buffered.pos);
+        assert_eq!(0, buffered.inner.len());
+
+        // The value does not fit and is written whole after the buffered bytes
+        buffered.write_u64_le(0x0807060504030201).await.unwrap();
+        assert_eq!(8, buffered.pos);
+        buffered.write_i32_be(-1).await.unwrap();
+        buffered.flush().await.unwrap();
+        assert_eq!(
+            &[
+                0x01, 0x02, 0xFE, 0xFF, 0x03, 0x04, 0x05, 0x06, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+                0x07, 0x08, 0xFF, 0xFF, 0xFF, 0xFF
+            ],
+            inner.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn can_write_integers_larger_than_buffer() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 2];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        buffered.write_u16_le(0x0201).await.unwrap();
+        buffered.write_u32_le(0x06050403).await.unwrap();
+        assert_eq!(0, buffered.pos);
+        assert_eq!(&[1, 2, 3, 4, 5, 6], buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_reconstruct_from_parts() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        assert!(buffered.get_ref().is_empty());
+        buffered.get_mut().push(0);
+
+        let (inner, buf, written) = buffered.into_parts();
+        let mut buffered = BufferedWrite::new_with_data(inner, buf, written);
+        buffered.flush().await.unwrap();
+        assert_eq!(&[0, 1, 2], buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_write_byte_without_capacity() {
+        let mut inner = Vec::new();
+        let mut buffered = BufferedWrite::new(&mut inner, &mut []);
+
+        buffered.write_byte(1).await.unwrap();
+        buffered.write_byte(2).await.unwrap();
+        assert_eq!(&[1, 2], buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_write_with_owned_buffer() {
+        let mut buffered = BufferedWriteN::with_storage(Vec::new(), [0; 4]);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        assert_eq!(2, buffered.written());
+        buffered.clear();
+
+        assert_eq!(2, buffered.write(&[3, 4]).await.unwrap());
+        assert_eq!(2, buffered.write(&[5, 6, 7]).await.unwrap());
+        assert_eq!(0, buffered.written());
+        assert_eq!(1, buffered.write(&[7]).await.unwrap());
+
+        let inner = buffered.finish().await.unwrap();
+        assert_eq!(&[3, 4, 5, 6, 7], inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_flush_and_bypass() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 8];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        assert!(buffered.bypass().is_err());
+
+        let inner = buffered.flush_and_bypass().await.unwrap();
+        inner.write_all(&[3, 4]).await.unwrap();
+        assert!(buffered.is_empty());
+        assert_eq!(&[1, 2, 3, 4], buffered.inner.as_slice());
+    }
+
+    #[tokio::test]
+    async fn can_release_with_pending() {
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(Vec::new(), &mut buf);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        assert_eq!(2, buffered.write(&[3, 4, 5]).await.unwrap());
+        assert_eq!(1, buffered.write(&[5]).await.unwrap());
+
+        let (inner, pending) = buffered.release_with_pending();
+        assert_eq!(&[1, 2, 3, 4], inner.as_slice());
+        assert_eq!(&[5], pending);
+
+        let mut buffered = BufferedWrite::with_storage(Vec::new(), &mut buf);
+        assert_eq!(1, buffered.write(&[6]).await.unwrap());
+        assert_eq!(&[6], buffered.release_with_pending().1);
+    }
+
+    #[tokio::test]
+    async fn counts_inner_writes_and_flushes() {
+        let mut inner = Vec::new();
+        let mut buf = [0; 4];
+        let mut buffered = BufferedWrite::new(&mut inner, &mut buf);
+
+        assert_eq!(2, buffered.write(&[1, 2]).await.unwrap());
+        assert_eq!(0, buffered.inner_writes());
+        assert_eq!(2, buffered.write(&[3, 4, 5]).await.unwrap());
+        assert_eq!(1, buffered.inner_writes());
+
+        buffered.flush().await.unwrap();
+        assert_eq!(1, buffered.inner_writes());
+        assert_eq!(1, buffered.inner_flushes());
+
+        buffered.write_all(&[5, 6, 7, 8]).await.unwrap();
+        assert_eq!(2, buffered.inner_writes());
+        assert_eq!(1, buffered.inner_flushes());
+
+        buffered.write_array([9, 10, 11, 12, 13]).await.unwrap();
+        assert_eq!(3, buffered.inner_writes());
+        assert_eq!(
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+            buffered.inner.as_slice()
+        );
+    }
+
+    #[test]
+    fn new_coerces_array_to_slice() {
+        fn use_default(_: BufferedWrite<'_, Vec<u8>>) {}
+
+        let mut buf = [0; 4];
+        use_default(BufferedWrite::new(Vec::new(), &mut buf));
     }
 
     #[tokio::test]
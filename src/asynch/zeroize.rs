@@ -0,0 +1,51 @@
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// Buffer storage that overwrites the buffer with zeros when dropped
+///
+/// See [`super::BufferedRead::zeroize_on_drop`] and [`super::BufferedWrite::zeroize_on_drop`].
+pub struct ZeroizeOnDrop<B: AsMut<[u8]>>(B);
+
+impl<B: AsMut<[u8]>> ZeroizeOnDrop<B> {
+    /// Wrap a buffer that should be zeroed when dropped
+    pub fn new(buf: B) -> Self {
+        Self(buf)
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> AsRef<[u8]> for ZeroizeOnDrop<B> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<B: AsMut<[u8]>> AsMut<[u8]> for ZeroizeOnDrop<B> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.0.as_mut()
+    }
+}
+
+impl<B: AsMut<[u8]>> Drop for ZeroizeOnDrop<B> {
+    fn drop(&mut self) {
+        for byte in self.0.as_mut() {
+            // Use volatile writes so that the zeroing is not optimized away
+            // SAFETY: The pointer is derived from a mutable reference and is therefore valid and aligned
+            unsafe { core::ptr::write_volatile(byte, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zeroes_buffer_when_dropped() {
+        let mut buf = [1, 2, 3, 4];
+        let mut zeroize = ZeroizeOnDrop::new(buf.as_mut_slice());
+        assert_eq!(&[1, 2, 3, 4], zeroize.as_mut());
+
+        drop(zeroize);
+        assert_eq!([0; 4], buf);
+    }
+}
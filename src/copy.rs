@@ -0,0 +1,90 @@
+use embedded_io::{BufRead, Write};
+
+/// Error returned when copying from a reader to a writer
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CopyError<R, W> {
+    /// Error returned by the reader.
+    Read(R),
+    /// Error returned by the writer.
+    Write(W),
+    /// The writer returned `Ok(0)` for a non-empty write.
+    WriteZero,
+}
+
+impl<R: core::fmt::Debug, W: core::fmt::Debug> core::fmt::Display for CopyError<R, W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Read(err) => write!(f, "read error: {:?}", err),
+            Self::Write(err) => write!(f, "write error: {:?}", err),
+            Self::WriteZero => f.write_str("writer returned Ok(0)"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: core::fmt::Debug, W: core::fmt::Debug> std::error::Error for CopyError<R, W> {}
+
+impl<R: embedded_io::Error, W: embedded_io::Error> embedded_io::Error for CopyError<R, W> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Self::Read(err) => err.kind(),
+            Self::Write(err) => err.kind(),
+            Self::WriteZero => embedded_io::ErrorKind::WriteZero,
+        }
+    }
+}
+
+/// Copy all bytes from a buffered reader to a writer until EOF
+///
+/// The slices returned by `fill_buf()` are written directly to the writer, and only the bytes that were
+/// actually written are consumed from the reader. The writer is not flushed.
+/// The total number of copied bytes is returned.
+pub fn copy_buf<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<u64, CopyError<R::Error, W::Error>> {
+    let mut copied = 0;
+    loop {
+        let buf = reader.fill_buf().map_err(CopyError::Read)?;
+        if buf.is_empty() {
+            return Ok(copied);
+        }
+
+        let written = writer.write(buf).map_err(CopyError::Write)?;
+        if written == 0 {
+            return Err(CopyError::WriteZero);
+        }
+        reader.consume(written);
+        copied += written as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_copy_buf() {
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut writer = Vec::new();
+
+        assert_eq!(6, copy_buf(&mut reader, &mut writer).unwrap());
+        assert!(reader.is_empty());
+        assert_eq!(&[1, 2, 3, 4, 5, 6], writer.as_slice());
+    }
+
+    #[test]
+    fn copy_buf_consumes_only_written_bytes() {
+        let mut reader: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut storage = [0; 4];
+        let mut writer = storage.as_mut_slice();
+
+        assert_eq!(
+            Err(CopyError::Write(embedded_io::SliceWriteError::Full)),
+            copy_buf(&mut reader, &mut writer)
+        );
+        assert_eq!(&[5, 6], reader);
+        assert_eq!(&[1, 2, 3, 4], &storage);
+    }
+}
@@ -1,3 +1,9 @@
 #![doc = include_str!("../README.md")]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod asynch;
+mod copy;
+
+pub use copy::{copy_buf, CopyError};